@@ -20,16 +20,26 @@ OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
 SOFTWARE.
  */
 
+/* local use */
+use yacrd::editor;
+use yacrd::reads2ovl;
+use yacrd::stack;
+use yacrd::summary;
+use yacrd::util;
+
 /// Yacrd use overlap between reads, to detect 'good' and 'bad' region,
 /// a region with coverage over the threshold is 'good' others are 'bad'.
 /// If read has a 'bad' region in middle this reads is mark as 'Chimeric'.
 /// If the ratio of 'bad' region length on total read length is larger than threshold this reads is marked as 'Not_covered'.
-
+/// With --foldback, if read is followed by its reverse complement this reads is marked as 'Foldback'.
+///
 /// Yacrd can make some other actions:
 /// - filter: for sequence or overlap file, record with reads marked as Chimeric or NotCovered isn't written in the output
 /// - extract: for sequence or overlap file, record contains reads marked as Chimeric or NotCovered is written in the output
 /// - split: for sequence file bad region in the middle of reads are removed, NotCovered read is removed
 /// - scrubb: for sequence file all bad region are removed, NotCovered read is removed
+/// - mask: for sequence file repeat regions found with --repeat-coverage are masked
+/// - convert: overlap file is converted in yacrd overlap file (.yovl), this file can be used as input to run detection again faster
 #[derive(clap::Parser, Debug)]
 #[clap(
     version = "1.0.0 Magby",
@@ -37,26 +47,138 @@ SOFTWARE.
     name = "yacrd"
 )]
 pub struct Command {
-    /// path to input file overlap (.paf|.m4|.mhap) or yacrd report (.yacrd), format is autodetected and compression input is allowed (gz|bzip2|lzma)
+    /// path to input file overlap (.paf|.m4|.mhap|.sam|.bam) or yacrd report (.yacrd|.jsonl) or yacrd overlap (.yovl), format is autodetected and compression input is allowed (gz|bzip2|lzma), use - for standard input
     #[clap(short = 'i', long = "input")]
     pub input: String,
 
-    /// path output file
+    /// path output file, use - for standard output
     #[clap(short = 'o', long = "output")]
     pub output: String,
 
+    /// format of report (yacrd|jsonl|bed), jsonl writes one json object by read, bed writes bad regions in BED6 with read type as name and junction score (times 1000) as score
+    #[clap(long = "report-format", default_value = "yacrd")]
+    pub report_format: editor::ReportFormat,
+
+    /// write coverage profile of each read in bedGraph in this file, compression of input is preserved, not available with yacrd report input
+    #[clap(long = "bedgraph")]
+    pub bedgraph: Option<String>,
+
+    /// write summary statistics of run in this file: reads and bases by read type, bad region length and position distributions, and with a subcommand that reads a fasta or fastq file (filter, extract, split, scrubb or mask) number of reads without overlap (no_overlap) and number of reads, bases and N50 before and after edition (sequences), these keys are absent otherwise
+    #[clap(long = "summary")]
+    pub summary: Option<String>,
+
+    /// format of summary (json|tsv)
+    #[clap(long = "summary-format", default_value = "json")]
+    pub summary_format: summary::SummaryFormat,
+
+    /// format of input (paf|m4|sam|bam|yovl|yacrd), by default format is detected with content or name of file
+    #[clap(long = "input-format")]
+    pub input_format: Option<util::FileType>,
+
     /// number of thread use by yacrd, 0 mean all threads available, default 1
     #[clap(short = 't', long = "thread")]
     pub threads: Option<usize>,
 
-    /// if coverage reach this value region is marked as bad
+    /// if coverage reach this value region is marked as bad, with auto this value is computed from modal coverage of all reads. auto isn't available in 'ondisk' and 'streaming' mode
     #[clap(short = 'c', long = "coverage", default_value = "0")]
-    pub coverage: u64,
+    pub coverage: stack::CoverageThreshold,
+
+    /// with --coverage auto, threshold is this fraction of modal coverage
+    #[clap(long = "auto-coverage-fraction", default_value = "0.1")]
+    pub auto_coverage_fraction: f64,
+
+    /// region with coverage lower than this fraction of the read median coverage is also marked as bad, for example 0.1 marks regions below 10% of read median, --coverage is still used as absolute minimum
+    #[clap(long = "relative-coverage")]
+    pub relative_coverage: Option<f64>,
+
+    /// a region where coverage reach --coverage is extended on both sides while coverage is lower or equal to this value, by default --coverage is used
+    #[clap(long = "leave-coverage")]
+    pub leave_coverage: Option<u64>,
+
+    /// bad regions shorter than this value are ignored
+    #[clap(long = "min-bad-length", default_value = "0")]
+    pub min_bad_length: u32,
+
+    /// bad regions separated by this distance or less are merged
+    #[clap(long = "merge-distance", default_value = "0")]
+    pub merge_distance: u32,
+
+    /// region with coverage upper or equal to this value times the read median coverage is reported as repeat in a fifth column of report, read without bad region in middle and with a repeat region is marked as Repeat
+    #[clap(long = "repeat-coverage")]
+    pub repeat_coverage: Option<f64>,
 
     /// if the ratio of bad region length on total length is lower than this value, read is marked as NotCovered
     #[clap(short = 'n', long = "not-coverage", default_value = "0.8")]
     pub not_coverage: f64,
 
+    /// paf, sam and bam overlaps with a mapping quality lower than this value are ignored, mapping quality 255 (unavailable) isn't filtered
+    #[clap(long = "min-mapq", default_value = "0")]
+    pub min_mapq: u8,
+
+    /// paf, m4, sam and bam overlaps with an alignment block length lower than this value are ignored
+    #[clap(long = "min-block-length", default_value = "0")]
+    pub min_block_length: u64,
+
+    /// paf, m4, sam and bam overlaps with an identity (number of matches divided by alignment block length) lower than this value are ignored, for sam and bam matches are computed with NM tag or =/X CIGAR operations
+    #[clap(long = "min-identity", default_value = "0")]
+    pub min_identity: f64,
+
+    /// detect foldback reads, reads followed by their reverse complement, with strand and partners of overlaps, sequence after fold is marked as bad. Not available in 'ondisk' and 'streaming' mode
+    #[clap(long = "foldback", conflicts_with_all = &["ondisk", "streaming"])]
+    pub foldback: bool,
+
+    /// compute a confidence score between 0 and 1 for each bad region in middle of reads, score is written after bad region in report
+    #[clap(long = "junction-score")]
+    pub junction_score: bool,
+
+    /// bounds of bad regions in middle of reads are replaced by the consensus breakpoint of overlaps stopping near each bound, a confidence interval of each bound is written after bad region in report
+    #[clap(long = "refine-junction")]
+    pub refine_junction: bool,
+
+    /// detect chimeric junctions with partners of overlaps, a position where partners covering left side and partners covering right side are almost different is marked as bad, even if coverage stays high. Not available in 'ondisk' and 'streaming' mode
+    #[clap(long = "partner-junction", conflicts_with_all = &["ondisk", "streaming"])]
+    pub partner_junction: bool,
+
+    /// only paf records with these alignment types (tp:A tag) are counted, for example P to keep only primary alignments, by default all records are counted
+    #[clap(long = "alignment-type", value_delimiter = ',')]
+    pub alignment_type: Vec<char>,
+
+    /// paf records must satisfy all these conditions on tags, for example "dv:f<0.2,NM:i<=100", record without the tag isn't filtered
+    #[clap(long = "tag-filter", value_delimiter = ',')]
+    pub tag_filter: Vec<reads2ovl::TagCondition>,
+
+    /// number of bases trimmed on each side of overlaps not at a read end, aligners extend alignments past chimeric junctions
+    #[clap(long = "trim-overlap", default_value = "0")]
+    pub trim_overlap: u32,
+
+    /// fraction of overlap length trimmed on each side of overlaps not at a read end, the largest of --trim-overlap and this value is used
+    #[clap(long = "trim-overlap-fraction", default_value = "0")]
+    pub trim_overlap_fraction: f64,
+
+    /// overlaps are split on insertions or deletions larger than this value, CIGAR come from cg:Z tag of paf or from sam/bam record
+    #[clap(long = "split-indel")]
+    pub split_indel: Option<u32>,
+
+    /// overlaps between a read and itself are kept
+    #[clap(long = "keep-self-overlap")]
+    pub keep_self_overlap: bool,
+
+    /// reciprocal overlaps (A→B and B→A) with intersecting intervals are counted only once, intervals of all overlaps are kept in memory
+    #[clap(long = "remove-duplicate", conflicts_with_all = &["ondisk", "streaming"])]
+    pub remove_duplicate: bool,
+
+    /// only overlaps of these classes (containment|dovetail|internal) are counted, by default all overlaps are counted
+    #[clap(long = "overlap-class", value_delimiter = ',')]
+    pub overlap_class: Vec<reads2ovl::OverlapClass>,
+
+    /// overlap with an overhang on one side larger than this value is classified as internal
+    #[clap(long = "max-overhang", default_value = "1000")]
+    pub max_overhang: u32,
+
+    /// overlap with an aligned length smaller than this fraction of aligned length plus overhangs is classified as internal
+    #[clap(long = "internal-fraction", default_value = "0.8")]
+    pub internal_fraction: f64,
+
     /// Control the size of the buffer used to read paf file
     #[clap(long = "read-buffer-size", default_value = "8192")]
     pub buffer_size: usize,
@@ -69,6 +191,10 @@ pub struct Command {
     #[clap(long = "ondisk-buffer-size", default_value = "64000000")]
     pub ondisk_buffer_size: String,
 
+    /// yacrd switches to 'streaming' mode, each read is reported as soon as all its overlaps are read, memory usage is bounded by the largest read pile-up. Overlaps of a read must be contiguous as query (read a), yacrd fails otherwise, and each overlap must be present in both directions. Subcommands can't be used in this mode
+    #[clap(long = "streaming", conflicts_with = "ondisk")]
+    pub streaming: bool,
+
     #[clap(subcommand)]
     pub subcmd: Option<SubCommand>,
 }
@@ -90,48 +216,110 @@ pub enum SubCommand {
     /// Record mark as chimeric or NotCovered is split
     #[clap()]
     Split(Split),
+
+    /// Repeat regions of read are masked, require --repeat-coverage or a report with repeat regions
+    #[clap()]
+    Mask(Mask),
+
+    /// Overlap file is converted in yacrd overlap file
+    #[clap()]
+    Convert(Convert),
 }
 
 #[derive(clap::Parser, Debug)]
 pub struct Scrubb {
-    /// path to sequence input (fasta|fastq), compression is autodetected (none|gzip|bzip2|lzma)
+    /// path to sequence input (fasta|fastq), compression is autodetected (none|gzip|bzip2|lzma), use - for standard input
     #[clap(short = 'i', long = "input", required = true)]
     pub input: String,
 
-    /// path to output file, format and compression of input is preserved
+    /// path to output file, format and compression of input is preserved, use - for standard output
     #[clap(short = 'o', long = "output", required = true)]
     pub output: String,
+
+    /// format of sequence input (fasta|fastq), by default format is detected with content or name of file
+    #[clap(long = "input-format")]
+    pub input_format: Option<util::FileType>,
+
+    /// only bad regions in middle of reads with a junction score upper or equal to this value are used, bad regions without score are always used
+    #[clap(long = "min-junction-score")]
+    pub min_junction_score: Option<f64>,
 }
 
 #[derive(clap::Parser, Debug)]
 pub struct Filter {
-    /// path to sequence input (fasta|fastq), compression is autodetected (none|gzip|bzip2|lzma)
+    /// path to sequence input (fasta|fastq), compression is autodetected (none|gzip|bzip2|lzma), use - for standard input
     #[clap(short = 'i', long = "input", required = true)]
     pub input: String,
 
-    /// path to output file, format and compression of input is preserved
+    /// path to output file, format and compression of input is preserved, use - for standard output
     #[clap(short = 'o', long = "output", required = true)]
     pub output: String,
+
+    /// format of sequence input (fasta|fastq|paf|m4), by default format is detected with content or name of file
+    #[clap(long = "input-format")]
+    pub input_format: Option<util::FileType>,
+
+    /// only bad regions in middle of reads with a junction score upper or equal to this value are used, bad regions without score are always used
+    #[clap(long = "min-junction-score")]
+    pub min_junction_score: Option<f64>,
 }
 
 #[derive(clap::Parser, Debug)]
 pub struct Extract {
-    /// path to sequence input (fasta|fastq), compression is autodetected (none|gzip|bzip2|lzma)
+    /// path to sequence input (fasta|fastq), compression is autodetected (none|gzip|bzip2|lzma), use - for standard input
     #[clap(short = 'i', long = "input", required = true)]
     pub input: String,
 
-    /// path to output file, format and compression of input is preserved
+    /// path to output file, format and compression of input is preserved, use - for standard output
     #[clap(short = 'o', long = "output", required = true)]
     pub output: String,
+
+    /// format of sequence input (fasta|fastq|paf|m4), by default format is detected with content or name of file
+    #[clap(long = "input-format")]
+    pub input_format: Option<util::FileType>,
 }
 
 #[derive(clap::Parser, Debug)]
 pub struct Split {
-    /// path to sequence input (fasta|fastq), compression is autodetected (none|gzip|bzip2|lzma)
+    /// path to sequence input (fasta|fastq), compression is autodetected (none|gzip|bzip2|lzma), use - for standard input
+    #[clap(short = 'i', long = "input", required = true)]
+    pub input: String,
+
+    /// path to output file, format and compression of input is preserved, use - for standard output
+    #[clap(short = 'o', long = "output", required = true)]
+    pub output: String,
+
+    /// format of sequence input (fasta|fastq), by default format is detected with content or name of file
+    #[clap(long = "input-format")]
+    pub input_format: Option<util::FileType>,
+
+    /// only bad regions in middle of reads with a junction score upper or equal to this value are used, bad regions without score are always used
+    #[clap(long = "min-junction-score")]
+    pub min_junction_score: Option<f64>,
+}
+
+#[derive(clap::Parser, Debug)]
+pub struct Mask {
+    /// path to sequence input (fasta|fastq), compression is autodetected (none|gzip|bzip2|lzma), use - for standard input
     #[clap(short = 'i', long = "input", required = true)]
     pub input: String,
 
-    /// path to output file, format and compression of input is preserved
+    /// path to output file, format and compression of input is preserved, use - for standard output
+    #[clap(short = 'o', long = "output", required = true)]
+    pub output: String,
+
+    /// format of sequence input (fasta|fastq), by default format is detected with content or name of file
+    #[clap(long = "input-format")]
+    pub input_format: Option<util::FileType>,
+
+    /// repeat regions are replaced by N, by default they are written in lowercase
+    #[clap(long = "hard")]
+    pub hard: bool,
+}
+
+#[derive(clap::Parser, Debug)]
+pub struct Convert {
+    /// path to yacrd overlap output (.yovl), this file can be used as input of yacrd in place of overlap file, use - for standard output
     #[clap(short = 'o', long = "output", required = true)]
     pub output: String,
 }
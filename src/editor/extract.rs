@@ -20,29 +20,34 @@ OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
 SOFTWARE.
  */
 
+//! Keep only records of reads marked as Chimeric, NotCovered or Foldback
+
 /* crate use */
 use anyhow::{anyhow, bail, Context, Result};
 
 /* local use */
-use crate::editor;
 use crate::error;
 use crate::stack;
+use crate::summary;
 use crate::util;
 
+/// Write records of `input_path` with a read marked as Chimeric, NotCovered or Foldback in `output_path`, format and compression are preserved
 pub fn extract(
     input_path: &str,
     output_path: &str,
+    input_format: Option<util::FileType>,
     badregions: &mut dyn stack::BadPart,
     not_covered: f64,
+    summary: &mut summary::Summary,
     buffer_size: usize,
 ) -> Result<()> {
-    let (input, compression) = util::read_file(input_path, buffer_size)?;
+    let (mut input, compression) = util::read_file(input_path, buffer_size)?;
     let output = util::write_file(output_path, compression, buffer_size)?;
 
-    match util::get_file_type(input_path) {
-        Some(util::FileType::Fasta) => fasta(input, output, badregions, not_covered)
+    match input_format.or_else(|| util::detect_file_type(&mut input, input_path)) {
+        Some(util::FileType::Fasta) => fasta(input, output, badregions, not_covered, summary)
             .with_context(|| anyhow!("Filename: {}", input_path.to_string()))?,
-        Some(util::FileType::Fastq) => fastq(input, output, badregions, not_covered)
+        Some(util::FileType::Fastq) => fastq(input, output, badregions, not_covered, summary)
             .with_context(|| anyhow!("Filename: {}", input_path.to_string()))?,
         Some(util::FileType::Paf) => paf(input, output, badregions, not_covered)
             .with_context(|| anyhow!("Filename: {}", input_path.to_string()))?,
@@ -53,7 +58,22 @@ pub fn extract(
             filetype: util::FileType::Yacrd,
             filename: input_path.to_string()
         }),
-        None | Some(util::FileType::YacrdOverlap) => {
+        Some(util::FileType::Sam) => bail!(error::Error::CantRunOperationOnFile {
+            operation: "extract".to_string(),
+            filetype: util::FileType::Sam,
+            filename: input_path.to_string()
+        }),
+        Some(util::FileType::Bam) => bail!(error::Error::CantRunOperationOnFile {
+            operation: "extract".to_string(),
+            filetype: util::FileType::Bam,
+            filename: input_path.to_string()
+        }),
+        Some(util::FileType::YacrdOverlap) => bail!(error::Error::CantRunOperationOnFile {
+            operation: "extract".to_string(),
+            filetype: util::FileType::YacrdOverlap,
+            filename: input_path.to_string()
+        }),
+        None => {
             bail!(error::Error::UnableToDetectFileFormat {
                 filename: input_path.to_string()
             })
@@ -68,6 +88,7 @@ fn fasta<R, W>(
     output: W,
     badregions: &mut dyn stack::BadPart,
     not_covered: f64,
+    summary: &mut summary::Summary,
 ) -> Result<()>
 where
     R: std::io::Read,
@@ -83,12 +104,14 @@ where
             format: util::FileType::Fasta,
         })?;
 
-        let (badregion, length) = badregions
-            .get_bad_part(&unsafe { String::from_utf8_unchecked(record.name().to_vec()) })?;
+        let id = unsafe { String::from_utf8_unchecked(record.name().to_vec()) };
 
-        let rtype = editor::type_of_read(*length, badregion, not_covered);
+        let rtype = badregions.get_read_type(&id, not_covered)?;
+        let (_, length) = badregions.get_bad_part(&id)?;
+        summary.add_input(record.sequence().len(), *length != 0);
 
-        if rtype != editor::ReadType::NotBad {
+        if rtype.is_bad() {
+            summary.add_output(record.sequence().len());
             writer
                 .write_record(&record)
                 .with_context(|| error::Error::WritingErrorNoFilename {
@@ -105,6 +128,7 @@ fn fastq<R, W>(
     output: W,
     badregions: &mut dyn stack::BadPart,
     not_covered: f64,
+    summary: &mut summary::Summary,
 ) -> Result<()>
 where
     R: std::io::Read,
@@ -120,16 +144,17 @@ where
             format: util::FileType::Fastq,
         })?;
 
-        let (badregion, length) = badregions.get_bad_part(
-            std::str::from_utf8(record.name())?
-                .split_ascii_whitespace()
-                .next()
-                .unwrap(),
-        )?;
+        let id = std::str::from_utf8(record.name())?
+            .split_ascii_whitespace()
+            .next()
+            .unwrap();
 
-        let rtype = editor::type_of_read(*length, badregion, not_covered);
+        let rtype = badregions.get_read_type(id, not_covered)?;
+        let (_, length) = badregions.get_bad_part(id)?;
+        summary.add_input(record.sequence().len(), *length != 0);
 
-        if rtype != editor::ReadType::NotBad {
+        if rtype.is_bad() {
+            summary.add_output(record.sequence().len());
             writer
                 .write_record(&record)
                 .with_context(|| error::Error::WritingErrorNoFilename {
@@ -168,13 +193,11 @@ where
         let id_a = record[0].to_string();
         let id_b = record[5].to_string();
 
-        let (badregion, length) = badregions.get_bad_part(&id_a)?;
-        let rtype_a = editor::type_of_read(*length, badregion, not_covered);
+        let rtype_a = badregions.get_read_type(&id_a, not_covered)?;
 
-        let (badregion, length) = badregions.get_bad_part(&id_b)?;
-        let rtype_b = editor::type_of_read(*length, badregion, not_covered);
+        let rtype_b = badregions.get_read_type(&id_b, not_covered)?;
 
-        if rtype_a != editor::ReadType::NotBad || rtype_b != editor::ReadType::NotBad {
+        if rtype_a.is_bad() || rtype_b.is_bad() {
             writer
                 .write_record(&record)
                 .with_context(|| error::Error::WritingErrorNoFilename {
@@ -213,13 +236,11 @@ where
         let id_a = record[0].to_string();
         let id_b = record[1].to_string();
 
-        let (badregion, length) = badregions.get_bad_part(&id_a)?;
-        let rtype_a = editor::type_of_read(*length, badregion, not_covered);
+        let rtype_a = badregions.get_read_type(&id_a, not_covered)?;
 
-        let (badregion, length) = badregions.get_bad_part(&id_b)?;
-        let rtype_b = editor::type_of_read(*length, badregion, not_covered);
+        let rtype_b = badregions.get_read_type(&id_b, not_covered)?;
 
-        if rtype_a != editor::ReadType::NotBad || rtype_b != editor::ReadType::NotBad {
+        if rtype_a.is_bad() || rtype_b.is_bad() {
             writer
                 .write_record(&record)
                 .with_context(|| error::Error::WritingErrorNoFilename {
@@ -232,6 +253,7 @@ where
 }
 
 #[cfg(test)]
+#[allow(clippy::redundant_static_lifetimes, clippy::unused_unit)]
 mod tests {
     use super::*;
 
@@ -265,7 +287,14 @@ ACTG
         stack.compute_all_bad_part();
 
         let mut output: Vec<u8> = Vec::new();
-        fasta(FASTA_FILE, &mut output, &mut stack, 0.8).unwrap();
+        fasta(
+            FASTA_FILE,
+            &mut output,
+            &mut stack,
+            0.8,
+            &mut summary::Summary::new(),
+        )
+        .unwrap();
 
         assert_eq!(FASTA_FILE_EXTRACTED, &output[..]);
     }
@@ -303,7 +332,14 @@ ACTG
         stack.compute_all_bad_part();
 
         let mut output: Vec<u8> = Vec::new();
-        fastq(FASTQ_FILE, &mut output, &mut stack, 0.8).unwrap();
+        fastq(
+            FASTQ_FILE,
+            &mut output,
+            &mut stack,
+            0.8,
+            &mut summary::Summary::new(),
+        )
+        .unwrap();
 
         assert_eq!(FASTQ_FILE_EXTRACTED, &output[..]);
     }
@@ -0,0 +1,297 @@
+/*
+Copyright (c) 2020 Pierre Marijon <pmarijon@mpi-inf.mpg.de>
+
+Permission is hereby granted, free of charge, to any person obtaining a copy
+of this software and associated documentation files (the "Software"), to deal
+in the Software without restriction, including without limitation the rights
+to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+copies of the Software, and to permit persons to whom the Software is
+furnished to do so, subject to the following conditions:
+
+The above copyright notice and this permission notice shall be included in all
+copies or substantial portions of the Software.
+
+THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+SOFTWARE.
+ */
+
+//! Mask repeat regions of reads
+
+/* crate use */
+use anyhow::{anyhow, bail, Context, Result};
+use log::error;
+
+/* local use */
+use crate::error;
+use crate::stack;
+use crate::summary;
+use crate::util;
+
+/// Write sequences of `input_path` with repeat regions masked in `output_path`, repeat regions are written in lowercase or replaced by N if `hard` is true
+pub fn mask(
+    input_path: &str,
+    output_path: &str,
+    input_format: Option<util::FileType>,
+    badregions: &mut dyn stack::BadPart,
+    hard: bool,
+    summary: &mut summary::Summary,
+    buffer_size: usize,
+) -> Result<()> {
+    let (mut input, compression) = util::read_file(input_path, buffer_size)?;
+    let output = util::write_file(output_path, compression, buffer_size)?;
+
+    match input_format.or_else(|| util::detect_file_type(&mut input, input_path)) {
+        Some(util::FileType::Fasta) => fasta(input, output, badregions, hard, summary)
+            .with_context(|| anyhow!("Filename: {}", input_path.to_string()))?,
+        Some(util::FileType::Fastq) => fastq(input, output, badregions, hard, summary)
+            .with_context(|| anyhow!("Filename: {}", input_path.to_string()))?,
+        Some(filetype) => bail!(error::Error::CantRunOperationOnFile {
+            operation: "mask".to_string(),
+            filetype,
+            filename: input_path.to_string()
+        }),
+        None => {
+            bail!(error::Error::UnableToDetectFileFormat {
+                filename: input_path.to_string()
+            })
+        }
+    };
+
+    Ok(())
+}
+
+fn mask_sequence(id: &str, sequence: &mut [u8], repeats: &[(u32, u32)], hard: bool) {
+    for (begin, end) in repeats {
+        if *end as usize > sequence.len() {
+            error!("For read {} repeat position is larger than read, it's strange check your data. For this read, this repeat and next are ignore.", id);
+            break;
+        }
+
+        for base in &mut sequence[(*begin as usize)..(*end as usize)] {
+            if hard {
+                *base = b'N';
+            } else {
+                base.make_ascii_lowercase();
+            }
+        }
+    }
+}
+
+fn fasta<R, W>(
+    input: R,
+    output: W,
+    badregions: &mut dyn stack::BadPart,
+    hard: bool,
+    summary: &mut summary::Summary,
+) -> Result<()>
+where
+    R: std::io::Read,
+    W: std::io::Write,
+{
+    let mut reader = noodles::fasta::Reader::new(std::io::BufReader::new(input));
+    let mut writer = noodles::fasta::Writer::new(std::io::BufWriter::new(output));
+
+    for result in reader.records() {
+        let record = result.with_context(|| error::Error::ReadingErrorNoFilename {
+            format: util::FileType::Fasta,
+        })?;
+
+        let id = unsafe { String::from_utf8_unchecked(record.name().to_vec()) };
+
+        let (_, length) = badregions.get_bad_part(&id)?;
+        summary.add_input(record.sequence().len(), *length != 0);
+        summary.add_output(record.sequence().len());
+
+        match badregions.get_repeat_part(&id) {
+            Some(repeats) if !repeats.is_empty() => {
+                let mut sequence = record.sequence().as_ref().to_vec();
+                mask_sequence(&id, &mut sequence, repeats, hard);
+
+                writer
+                    .write_record(&noodles::fasta::Record::new(
+                        record.definition().clone(),
+                        noodles::fasta::record::Sequence::from(sequence),
+                    ))
+                    .with_context(|| error::Error::WritingErrorNoFilename {
+                        format: util::FileType::Fasta,
+                    })?;
+            }
+            _ => writer.write_record(&record).with_context(|| {
+                error::Error::WritingErrorNoFilename {
+                    format: util::FileType::Fasta,
+                }
+            })?,
+        }
+    }
+
+    Ok(())
+}
+
+fn fastq<R, W>(
+    input: R,
+    output: W,
+    badregions: &mut dyn stack::BadPart,
+    hard: bool,
+    summary: &mut summary::Summary,
+) -> Result<()>
+where
+    R: std::io::Read,
+    W: std::io::Write,
+{
+    let mut reader = noodles::fastq::Reader::new(std::io::BufReader::new(input));
+    let mut writer = noodles::fastq::Writer::new(std::io::BufWriter::new(output));
+
+    for result in reader.records() {
+        let mut record = result.with_context(|| error::Error::ReadingErrorNoFilename {
+            format: util::FileType::Fastq,
+        })?;
+
+        let id = std::str::from_utf8(record.name())?
+            .split_ascii_whitespace()
+            .next()
+            .unwrap()
+            .to_string();
+
+        let (_, length) = badregions.get_bad_part(&id)?;
+        summary.add_input(record.sequence().len(), *length != 0);
+        summary.add_output(record.sequence().len());
+
+        if let Some(repeats) = badregions.get_repeat_part(&id) {
+            mask_sequence(&id, record.sequence_mut(), repeats, hard);
+        }
+
+        writer
+            .write_record(&record)
+            .with_context(|| error::Error::WritingErrorNoFilename {
+                format: util::FileType::Fastq,
+            })?;
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::stack::BadPart;
+
+    use crate::reads2ovl;
+    use crate::reads2ovl::Reads2Ovl;
+
+    const FASTA_FILE: &[u8] = b">1
+ACTGGGGGGACTGGGGGGACTG
+>2
+ACTG
+";
+
+    const FASTA_FILE_SOFT: &[u8] = b">1
+ACTGGggggactgggGGGACTG
+>2
+ACTG
+";
+
+    const FASTA_FILE_HARD: &[u8] = b">1
+ACTGGNNNNNNNNNNGGGACTG
+>2
+ACTG
+";
+
+    const FASTQ_FILE: &[u8] = b"@1
+ACTGGGGGGACTGGGGGGACTG
++
+??????????????????????
+@2
+ACTG
++
+????
+";
+
+    const FASTQ_FILE_SOFT: &[u8] = b"@1
+ACTGGggggactgggGGGACTG
++
+??????????????????????
+@2
+ACTG
++
+????
+";
+
+    fn stack() -> stack::FromOverlap {
+        let mut ovlst = reads2ovl::FullMemory::new(8192);
+
+        ovlst.add_length("1".to_string(), 22);
+        ovlst.add_overlap("1".to_string(), (0, 22)).unwrap();
+        for _ in 0..3 {
+            ovlst.add_overlap("1".to_string(), (5, 15)).unwrap();
+        }
+
+        let mut stack = stack::FromOverlap::with_params(
+            Box::new(ovlst),
+            stack::DetectionParams {
+                repeat_coverage: Some(3.0),
+                ..stack::DetectionParams::new(0)
+            },
+        );
+
+        stack.compute_all_bad_part();
+
+        stack
+    }
+
+    #[test]
+    fn fasta_soft() {
+        let mut stack = stack();
+
+        let mut output: Vec<u8> = Vec::new();
+        fasta(
+            FASTA_FILE,
+            &mut output,
+            &mut stack,
+            false,
+            &mut summary::Summary::new(),
+        )
+        .unwrap();
+
+        assert_eq!(FASTA_FILE_SOFT, &output[..]);
+    }
+
+    #[test]
+    fn fasta_hard() {
+        let mut stack = stack();
+
+        let mut output: Vec<u8> = Vec::new();
+        fasta(
+            FASTA_FILE,
+            &mut output,
+            &mut stack,
+            true,
+            &mut summary::Summary::new(),
+        )
+        .unwrap();
+
+        assert_eq!(FASTA_FILE_HARD, &output[..]);
+    }
+
+    #[test]
+    fn fastq_soft() {
+        let mut stack = stack();
+
+        let mut output: Vec<u8> = Vec::new();
+        fastq(
+            FASTQ_FILE,
+            &mut output,
+            &mut stack,
+            false,
+            &mut summary::Summary::new(),
+        )
+        .unwrap();
+
+        assert_eq!(FASTQ_FILE_SOFT, &output[..]);
+    }
+}
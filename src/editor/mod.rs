@@ -20,68 +20,296 @@ OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
 SOFTWARE.
  */
 
+//! Classify reads, write report and edit sequence or overlap files with bad regions
+
 /* local mod */
 pub mod extract;
 pub mod filter;
+pub mod mask;
 pub mod scrubbing;
 pub mod split;
 
 /* stuff declare in submod need to be accessible from mod level */
 pub use self::extract::*;
 pub use self::filter::*;
+pub use self::mask::*;
 pub use self::scrubbing::*;
 pub use self::split::*;
 
 /* crate use */
 use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
 
 /* local use */
 use crate::error;
+use crate::stack;
 use crate::util;
 
+/// Classification of a read
 #[derive(Debug, PartialEq)]
 pub enum ReadType {
+    /// read contains a bad region in middle
     Chimeric,
+    /// ratio of bad region length on read length is larger than threshold
     NotCovered,
+    /// read is followed by its reverse complement, one half is supported only by forward overlaps
+    /// and the other half only by reverse overlaps with same partners
+    Foldback,
+    /// read isn't Chimeric, NotCovered or Foldback but contains a region with high coverage
+    Repeat,
+    /// read isn't Chimeric or NotCovered
     NotBad,
 }
 
 impl Eq for ReadType {}
 
 impl ReadType {
+    /// Name of read type used in report
     pub fn as_str(&self) -> &'static str {
         match self {
             ReadType::Chimeric => "Chimeric",
             ReadType::NotCovered => "NotCovered",
+            ReadType::Foldback => "Foldback",
+            ReadType::Repeat => "Repeat",
             ReadType::NotBad => "NotBad",
         }
     }
+
+    /// Return true if read is Chimeric, NotCovered or Foldback
+    pub fn is_bad(&self) -> bool {
+        !matches!(self, ReadType::Repeat | ReadType::NotBad)
+    }
+
+    /// Replace NotBad by Repeat if `repeat` is true
+    pub fn with_repeat(self, repeat: bool) -> Self {
+        match self {
+            ReadType::NotBad if repeat => ReadType::Repeat,
+            _ => self,
+        }
+    }
+}
+
+/// Format of report
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ReportFormat {
+    /// tabulated yacrd report
+    Yacrd,
+    /// one json object by read
+    Jsonl,
+    /// BED6, one line by bad region
+    Bed,
+}
+
+impl std::str::FromStr for ReportFormat {
+    type Err = String;
+
+    fn from_str(name: &str) -> std::result::Result<Self, Self::Err> {
+        match name.to_lowercase().as_str() {
+            "yacrd" => Ok(ReportFormat::Yacrd),
+            "jsonl" => Ok(ReportFormat::Jsonl),
+            "bed" => Ok(ReportFormat::Bed),
+            _ => Err(format!(
+                "unknown report format {}, possible values are yacrd, jsonl or bed",
+                name
+            )),
+        }
+    }
+}
+
+/// Annotations of a read written in report with its bad regions, None if they aren't computed
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct ReadAnnotations<'a> {
+    /// annotation of each bad region
+    pub junctions: Option<&'a [stack::Junction]>,
+    /// repeat regions
+    pub repeats: Option<&'a [(u32, u32)]>,
+    /// coverage statistics
+    pub stats: Option<stack::ReadStats>,
+}
+
+/// Function writing a read in report, see [report]
+pub type ReportWriter<W> =
+    fn(&str, usize, &[(u32, u32)], &ReadAnnotations, &ReadType, &mut W) -> Result<()>;
+
+impl ReportFormat {
+    /// Get function writing a read in this format
+    pub fn writer<W: std::io::Write>(&self) -> ReportWriter<W> {
+        match self {
+            ReportFormat::Yacrd => report,
+            ReportFormat::Jsonl => report_jsonl,
+            ReportFormat::Bed => report_bed,
+        }
+    }
+}
+
+/// A region of a read in jsonl report, with its junction annotation if it's present
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct JsonRegion {
+    /// begin of region
+    pub begin: u32,
+    /// end of region
+    pub end: u32,
+    /// length of region
+    pub length: u32,
+    /// junction score
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub score: Option<f64>,
+    /// confidence interval of region begin
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub begin_ci: Option<(u32, u32)>,
+    /// confidence interval of region end
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub end_ci: Option<(u32, u32)>,
+}
+
+/// A read in jsonl report
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct JsonRead {
+    /// type of read, see [ReadType::as_str]
+    #[serde(rename = "type")]
+    pub read_type: String,
+    /// name of read
+    pub name: String,
+    /// length of read
+    pub length: usize,
+    /// bad regions
+    pub bad_regions: Vec<JsonRegion>,
+    /// repeat regions, present only if repeat regions are detected
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub repeats: Option<Vec<JsonRegion>>,
+    /// coverage statistics, present only if they are computed
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub coverage: Option<stack::ReadStats>,
 }
 
+/// Write report line of a read, if junctions are set junction annotations are written after bad
+/// regions, if repeats are set repeat regions are written in a fifth column, coverage statistics
+/// aren't written
 pub fn report<W>(
     read: &str,
     length: usize,
     badregions: &[(u32, u32)],
-    not_covered: f64,
+    annotations: &ReadAnnotations,
+    readtype: &ReadType,
     out: &mut W,
 ) -> Result<()>
 where
     W: std::io::Write,
 {
-    let readtype = type_of_read(length, badregions, not_covered);
-    writeln!(
+    write!(
         out,
         "{}\t{}\t{}\t{}",
         readtype.as_str(),
         read,
         length,
-        bad_region_format(badregions)
+        bad_region_format(badregions, annotations.junctions.unwrap_or(&[]))
     )
     .with_context(|| error::Error::WritingErrorNoFilename {
         format: util::FileType::Yacrd,
+    })?;
+
+    if let Some(repeats) = annotations.repeats {
+        write!(out, "\t{}", bad_region_format(repeats, &[])).with_context(|| {
+            error::Error::WritingErrorNoFilename {
+                format: util::FileType::Yacrd,
+            }
+        })?;
+    }
+
+    writeln!(out).with_context(|| error::Error::WritingErrorNoFilename {
+        format: util::FileType::Yacrd,
+    })
+}
+
+/// Write a read as a json object in one line, arguments are the same as [report]
+pub fn report_jsonl<W>(
+    read: &str,
+    length: usize,
+    badregions: &[(u32, u32)],
+    annotations: &ReadAnnotations,
+    readtype: &ReadType,
+    out: &mut W,
+) -> Result<()>
+where
+    W: std::io::Write,
+{
+    let record = JsonRead {
+        read_type: readtype.as_str().to_string(),
+        name: read.to_string(),
+        length,
+        bad_regions: json_regions(badregions, annotations.junctions.unwrap_or(&[])),
+        repeats: annotations.repeats.map(|r| json_regions(r, &[])),
+        coverage: annotations.stats,
+    };
+
+    serde_json::to_writer(&mut *out, &record).with_context(|| {
+        error::Error::WritingErrorNoFilename {
+            format: util::FileType::Yacrd,
+        }
+    })?;
+
+    writeln!(out).with_context(|| error::Error::WritingErrorNoFilename {
+        format: util::FileType::Yacrd,
     })
 }
 
+/// Write bad regions of a read in BED6, read type is the name and junction score (times 1000) is
+/// the score, regions without score have score 0. Arguments are the same as [report], repeat
+/// regions and coverage statistics aren't written
+pub fn report_bed<W>(
+    read: &str,
+    _length: usize,
+    badregions: &[(u32, u32)],
+    annotations: &ReadAnnotations,
+    readtype: &ReadType,
+    out: &mut W,
+) -> Result<()>
+where
+    W: std::io::Write,
+{
+    let junctions = annotations.junctions.unwrap_or(&[]);
+
+    for (i, bad) in badregions.iter().enumerate() {
+        let score = junctions
+            .get(i)
+            .and_then(|j| j.score)
+            .map(|score| (score * 1000.0).round() as u32)
+            .unwrap_or(0);
+
+        writeln!(
+            out,
+            "{}\t{}\t{}\t{}\t{}\t.",
+            read,
+            bad.0,
+            bad.1,
+            readtype.as_str(),
+            score
+        )
+        .with_context(|| error::Error::WritingErrorNoFilename {
+            format: util::FileType::Yacrd,
+        })?;
+    }
+
+    Ok(())
+}
+
+/// Write coverage profile of a read in bedGraph, one line by region with the same coverage
+pub fn bedgraph<W>(read: &str, profile: &[(u32, u32, u32)], out: &mut W) -> Result<()>
+where
+    W: std::io::Write,
+{
+    for (begin, end, coverage) in profile {
+        writeln!(out, "{}\t{}\t{}\t{}", read, begin, end, coverage).with_context(|| {
+            error::Error::WritingErrorNoFilename {
+                format: util::FileType::Yacrd,
+            }
+        })?;
+    }
+
+    Ok(())
+}
+
+/// Compute type of a read with its length and bad regions
 pub fn type_of_read(length: usize, badregions: &[(u32, u32)], not_covered: f64) -> ReadType {
     let bad_region_len = badregions.iter().fold(0, |acc, x| acc + (x.1 - x.0));
 
@@ -99,13 +327,45 @@ pub fn type_of_read(length: usize, badregions: &[(u32, u32)], not_covered: f64)
     ReadType::NotBad
 }
 
-fn bad_region_format(bads: &[(u32, u32)]) -> String {
+fn bad_region_format(bads: &[(u32, u32)], junctions: &[stack::Junction]) -> String {
     bads.iter()
-        .map(|b| format!("{},{},{}", b.1 - b.0, b.0, b.1))
+        .enumerate()
+        .map(|(i, b)| {
+            let mut region = format!("{},{},{}", b.1 - b.0, b.0, b.1);
+
+            if let Some(junction) = junctions.get(i) {
+                if let Some(score) = junction.score {
+                    region.push_str(&format!(",{:.3}", score));
+                }
+                if let (Some(begin), Some(end)) = (junction.begin_ci, junction.end_ci) {
+                    region.push_str(&format!(",{}:{}:{}:{}", begin.0, begin.1, end.0, end.1));
+                }
+            }
+
+            region
+        })
         .collect::<Vec<String>>()
         .join(";")
 }
 
+fn json_regions(bads: &[(u32, u32)], junctions: &[stack::Junction]) -> Vec<JsonRegion> {
+    bads.iter()
+        .enumerate()
+        .map(|(i, b)| {
+            let junction = junctions.get(i).copied().unwrap_or_default();
+
+            JsonRegion {
+                begin: b.0,
+                end: b.1,
+                length: b.1 - b.0,
+                score: junction.score,
+                begin_ci: junction.begin_ci,
+                end_ci: junction.end_ci,
+            }
+        })
+        .collect()
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -126,4 +386,204 @@ mod tests {
         assert_eq!(ReadType::NotBad, type_of_read(e.1, &e.0, 0.8));
         assert_eq!(ReadType::Chimeric, type_of_read(f.1, &f.0, 0.8));
     }
+
+    #[test]
+    fn repeat_type() {
+        assert_eq!(ReadType::Repeat, ReadType::NotBad.with_repeat(true));
+        assert_eq!(ReadType::NotBad, ReadType::NotBad.with_repeat(false));
+        assert_eq!(ReadType::Chimeric, ReadType::Chimeric.with_repeat(true));
+        assert!(!ReadType::Repeat.is_bad());
+        assert!(ReadType::Foldback.is_bad());
+    }
+
+    #[test]
+    fn report_repeat() {
+        let mut out = Vec::new();
+
+        report(
+            "A",
+            1000,
+            &[(0, 10)],
+            &ReadAnnotations::default(),
+            &ReadType::NotBad,
+            &mut out,
+        )
+        .unwrap();
+        report(
+            "B",
+            1000,
+            &[],
+            &ReadAnnotations {
+                repeats: Some(&[(200, 300)]),
+                ..Default::default()
+            },
+            &ReadType::Repeat,
+            &mut out,
+        )
+        .unwrap();
+
+        assert_eq!(
+            b"NotBad\tA\t1000\t10,0,10\nRepeat\tB\t1000\t\t100,200,300\n",
+            &out[..]
+        );
+    }
+
+    #[test]
+    fn report_score() {
+        let mut out = Vec::new();
+
+        report(
+            "A",
+            1000,
+            &[(0, 10), (400, 500)],
+            &ReadAnnotations {
+                junctions: Some(&[
+                    stack::Junction::default(),
+                    stack::Junction {
+                        score: Some(0.8123),
+                        ..Default::default()
+                    },
+                ]),
+                ..Default::default()
+            },
+            &ReadType::Chimeric,
+            &mut out,
+        )
+        .unwrap();
+
+        report(
+            "B",
+            1000,
+            &[(400, 500)],
+            &ReadAnnotations {
+                junctions: Some(&[stack::Junction {
+                    score: None,
+                    begin_ci: Some((390, 410)),
+                    end_ci: Some((500, 520)),
+                }]),
+                ..Default::default()
+            },
+            &ReadType::Chimeric,
+            &mut out,
+        )
+        .unwrap();
+
+        assert_eq!(
+            b"Chimeric\tA\t1000\t10,0,10;100,400,500,0.812\nChimeric\tB\t1000\t100,400,500,390:410:500:520\n",
+            &out[..]
+        );
+    }
+
+    #[test]
+    fn report_json() {
+        let mut out = Vec::new();
+
+        report_jsonl(
+            "A",
+            1000,
+            &[(0, 10), (400, 500)],
+            &ReadAnnotations {
+                junctions: Some(&[
+                    stack::Junction::default(),
+                    stack::Junction {
+                        score: Some(0.5),
+                        begin_ci: Some((390, 410)),
+                        end_ci: Some((500, 520)),
+                    },
+                ]),
+                repeats: Some(&[]),
+                stats: Some(stack::ReadStats {
+                    median: 12,
+                    threshold: 3,
+                    relative_threshold: None,
+                    modal: Some(30),
+                }),
+            },
+            &ReadType::Chimeric,
+            &mut out,
+        )
+        .unwrap();
+        report_jsonl(
+            "B",
+            500,
+            &[],
+            &ReadAnnotations::default(),
+            &ReadType::NotBad,
+            &mut out,
+        )
+        .unwrap();
+
+        assert_eq!(
+            "{\"type\":\"Chimeric\",\"name\":\"A\",\"length\":1000,\"bad_regions\":[{\"begin\":0,\"end\":10,\"length\":10},{\"begin\":400,\"end\":500,\"length\":100,\"score\":0.5,\"begin_ci\":[390,410],\"end_ci\":[500,520]}],\"repeats\":[],\"coverage\":{\"median\":12,\"threshold\":3,\"modal\":30}}
+{\"type\":\"NotBad\",\"name\":\"B\",\"length\":500,\"bad_regions\":[]}
+",
+            String::from_utf8(out).unwrap()
+        );
+    }
+
+    #[test]
+    fn report_format() {
+        assert_eq!(Ok(ReportFormat::Yacrd), "yacrd".parse());
+        assert_eq!(Ok(ReportFormat::Jsonl), "JSONL".parse());
+        assert_eq!(Ok(ReportFormat::Bed), "bed".parse());
+        assert!("json".parse::<ReportFormat>().is_err());
+    }
+
+    #[test]
+    fn report_bed6() {
+        let mut out = Vec::new();
+
+        report_bed(
+            "A",
+            1000,
+            &[(0, 10), (400, 500)],
+            &ReadAnnotations {
+                junctions: Some(&[
+                    stack::Junction::default(),
+                    stack::Junction {
+                        score: Some(0.8124),
+                        begin_ci: None,
+                        end_ci: None,
+                    },
+                ]),
+                ..Default::default()
+            },
+            &ReadType::Chimeric,
+            &mut out,
+        )
+        .unwrap();
+        report_bed(
+            "B",
+            500,
+            &[],
+            &ReadAnnotations::default(),
+            &ReadType::NotBad,
+            &mut out,
+        )
+        .unwrap();
+        report_bed(
+            "C",
+            500,
+            &[(450, 500)],
+            &ReadAnnotations::default(),
+            &ReadType::NotBad,
+            &mut out,
+        )
+        .unwrap();
+
+        assert_eq!(
+            b"A\t0\t10\tChimeric\t0\t.\nA\t400\t500\tChimeric\t812\t.\nC\t450\t500\tNotBad\t0\t.\n",
+            &out[..]
+        );
+    }
+
+    #[test]
+    fn coverage_bedgraph() {
+        let mut out = Vec::new();
+
+        bedgraph("A", &[(0, 10, 0), (10, 400, 3), (400, 500, 1)], &mut out).unwrap();
+        bedgraph("B", &[], &mut out).unwrap();
+
+        assert_eq!(b"A\t0\t10\t0\nA\t10\t400\t3\nA\t400\t500\t1\n", &out[..]);
+    }
 }
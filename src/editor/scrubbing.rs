@@ -20,6 +20,8 @@ OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
 SOFTWARE.
  */
 
+//! Remove all bad regions of reads
+
 /* crate use */
 use anyhow::{anyhow, bail, Context, Result};
 use log::error;
@@ -28,22 +30,26 @@ use log::error;
 use crate::editor;
 use crate::error;
 use crate::stack;
+use crate::summary;
 use crate::util;
 
+/// Write sequences of `input_path` without bad regions in `output_path`, NotCovered reads are removed
 pub fn scrubbing(
     input_path: &str,
     output_path: &str,
+    input_format: Option<util::FileType>,
     badregions: &mut dyn stack::BadPart,
     not_covered: f64,
+    summary: &mut summary::Summary,
     buffer_size: usize,
 ) -> Result<()> {
-    let (input, compression) = util::read_file(input_path, buffer_size)?;
+    let (mut input, compression) = util::read_file(input_path, buffer_size)?;
     let output = util::write_file(output_path, compression, buffer_size)?;
 
-    match util::get_file_type(input_path) {
-        Some(util::FileType::Fasta) => fasta(input, output, badregions, not_covered)
+    match input_format.or_else(|| util::detect_file_type(&mut input, input_path)) {
+        Some(util::FileType::Fasta) => fasta(input, output, badregions, not_covered, summary)
             .with_context(|| anyhow!("Filename: {}", input_path.to_string()))?,
-        Some(util::FileType::Fastq) => fastq(input, output, badregions, not_covered)
+        Some(util::FileType::Fastq) => fastq(input, output, badregions, not_covered, summary)
             .with_context(|| anyhow!("Filename: {}", input_path.to_string()))?,
         Some(util::FileType::Paf) => bail!(error::Error::CantRunOperationOnFile {
             operation: "scrubbing".to_string(),
@@ -60,7 +66,22 @@ pub fn scrubbing(
             filetype: util::FileType::Yacrd,
             filename: input_path.to_string()
         }),
-        None | Some(util::FileType::YacrdOverlap) => {
+        Some(util::FileType::Sam) => bail!(error::Error::CantRunOperationOnFile {
+            operation: "scrubbing".to_string(),
+            filetype: util::FileType::Sam,
+            filename: input_path.to_string()
+        }),
+        Some(util::FileType::Bam) => bail!(error::Error::CantRunOperationOnFile {
+            operation: "scrubbing".to_string(),
+            filetype: util::FileType::Bam,
+            filename: input_path.to_string()
+        }),
+        Some(util::FileType::YacrdOverlap) => bail!(error::Error::CantRunOperationOnFile {
+            operation: "scrubbing".to_string(),
+            filetype: util::FileType::YacrdOverlap,
+            filename: input_path.to_string()
+        }),
+        None => {
             bail!(error::Error::UnableToDetectFileFormat {
                 filename: input_path.to_string()
             })
@@ -75,6 +96,7 @@ fn fasta<R, W>(
     output: W,
     badregions: &mut dyn stack::BadPart,
     not_covered: f64,
+    summary: &mut summary::Summary,
 ) -> Result<()>
 where
     R: std::io::Read,
@@ -88,14 +110,16 @@ where
             format: util::FileType::Fasta,
         })?;
 
-        let (badregion, length) = badregions
-            .get_bad_part(&unsafe { String::from_utf8_unchecked(record.name().to_vec()) })?;
+        let id = unsafe { String::from_utf8_unchecked(record.name().to_vec()) };
 
-        let rtype = editor::type_of_read(*length, badregion, not_covered);
+        let rtype = badregions.get_read_type(&id, not_covered)?;
+        let (badregion, length) = badregions.get_bad_part(&id)?;
+        summary.add_input(record.sequence().len(), *length != 0);
 
         if rtype == editor::ReadType::NotCovered {
             continue;
         } else if badregion.is_empty() {
+            summary.add_output(record.sequence().len());
             writer
                 .write_record(&record)
                 .with_context(|| error::Error::WritingErrorNoFilename {
@@ -126,6 +150,7 @@ where
                     break;
                 }
 
+                summary.add_output((pos[1] - pos[0]) as usize);
                 writer
                     .write_record(&noodles::fasta::Record::new(
                         noodles::fasta::record::Definition::new(
@@ -158,6 +183,7 @@ fn fastq<R, W>(
     output: W,
     badregions: &mut dyn stack::BadPart,
     not_covered: f64,
+    summary: &mut summary::Summary,
 ) -> Result<()>
 where
     R: std::io::Read,
@@ -171,18 +197,19 @@ where
             format: util::FileType::Fastq,
         })?;
 
-        let (badregion, length) = badregions.get_bad_part(
-            std::str::from_utf8(record.name())?
-                .split_ascii_whitespace()
-                .next()
-                .unwrap(),
-        )?;
+        let id = std::str::from_utf8(record.name())?
+            .split_ascii_whitespace()
+            .next()
+            .unwrap();
 
-        let rtype = editor::type_of_read(*length, badregion, not_covered);
+        let rtype = badregions.get_read_type(id, not_covered)?;
+        let (badregion, length) = badregions.get_bad_part(id)?;
+        summary.add_input(record.sequence().len(), *length != 0);
 
         if rtype == editor::ReadType::NotCovered {
             continue;
         } else if badregion.is_empty() {
+            summary.add_output(record.sequence().len());
             writer
                 .write_record(&record)
                 .with_context(|| error::Error::WritingErrorNoFilename {
@@ -216,6 +243,7 @@ where
                     break;
                 }
 
+                summary.add_output((pos[1] - pos[0]) as usize);
                 writer
                     .write_record(&noodles::fastq::Record::new(
                         noodles::fastq::record::Definition::new(
@@ -236,6 +264,7 @@ where
 }
 
 #[cfg(test)]
+#[allow(clippy::redundant_static_lifetimes, clippy::unused_unit)]
 mod tests {
     use super::*;
 
@@ -278,7 +307,14 @@ ACTG
         stack.compute_all_bad_part();
 
         let mut output: Vec<u8> = Vec::new();
-        fasta(FASTA_FILE, &mut output, &mut stack, 0.8).unwrap();
+        fasta(
+            FASTA_FILE,
+            &mut output,
+            &mut stack,
+            0.8,
+            &mut summary::Summary::new(),
+        )
+        .unwrap();
 
         assert_eq!(FASTA_FILE_SCRUBBED, &output[..]);
     }
@@ -303,7 +339,14 @@ ACTG
         stack.compute_all_bad_part();
 
         let mut output: Vec<u8> = Vec::new();
-        fasta(FASTA_FILE, &mut output, &mut stack, 0.8).unwrap();
+        fasta(
+            FASTA_FILE,
+            &mut output,
+            &mut stack,
+            0.8,
+            &mut summary::Summary::new(),
+        )
+        .unwrap();
 
         assert_eq!(FASTA_FILE_SCRUBBED2, &output[..]);
     }
@@ -358,7 +401,14 @@ ACTG
         stack.compute_all_bad_part();
 
         let mut output: Vec<u8> = Vec::new();
-        fastq(FASTQ_FILE, &mut output, &mut stack, 0.8).unwrap();
+        fastq(
+            FASTQ_FILE,
+            &mut output,
+            &mut stack,
+            0.8,
+            &mut summary::Summary::new(),
+        )
+        .unwrap();
 
         assert_eq!(FASTQ_FILE_SCRUBBED, &output[..]);
     }
@@ -389,7 +439,14 @@ ACTG
         stack.compute_all_bad_part();
 
         let mut output: Vec<u8> = Vec::new();
-        fastq(FASTQ_FILE, &mut output, &mut stack, 0.8).unwrap();
+        fastq(
+            FASTQ_FILE,
+            &mut output,
+            &mut stack,
+            0.8,
+            &mut summary::Summary::new(),
+        )
+        .unwrap();
 
         assert_eq!(FASTQ_FILE_SCRUBBED2, &output[..]);
     }
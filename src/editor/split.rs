@@ -20,6 +20,8 @@ OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
 SOFTWARE.
  */
 
+//! Split reads on bad regions in middle of reads
+
 /* crate use */
 use anyhow::{anyhow, bail, Context, Result};
 use log::error;
@@ -28,22 +30,26 @@ use log::error;
 use crate::editor;
 use crate::error;
 use crate::stack;
+use crate::summary;
 use crate::util;
 
+/// Write sequences of `input_path` split on bad regions in middle of read in `output_path`, NotCovered reads are removed and Foldback reads are cut at fold
 pub fn split(
     input_path: &str,
     output_path: &str,
+    input_format: Option<util::FileType>,
     badregions: &mut dyn stack::BadPart,
     not_covered: f64,
+    summary: &mut summary::Summary,
     buffer_size: usize,
 ) -> Result<()> {
-    let (input, compression) = util::read_file(input_path, buffer_size)?;
+    let (mut input, compression) = util::read_file(input_path, buffer_size)?;
     let output = util::write_file(output_path, compression, buffer_size)?;
 
-    match util::get_file_type(input_path) {
-        Some(util::FileType::Fasta) => fasta(input, output, badregions, not_covered)
+    match input_format.or_else(|| util::detect_file_type(&mut input, input_path)) {
+        Some(util::FileType::Fasta) => fasta(input, output, badregions, not_covered, summary)
             .with_context(|| anyhow!("Filename: {}", input_path.to_string()))?,
-        Some(util::FileType::Fastq) => fastq(input, output, badregions, not_covered)
+        Some(util::FileType::Fastq) => fastq(input, output, badregions, not_covered, summary)
             .with_context(|| anyhow!("Filename: {}", input_path.to_string()))?,
         Some(util::FileType::Paf) => bail!(error::Error::CantRunOperationOnFile {
             operation: "split".to_string(),
@@ -60,7 +66,22 @@ pub fn split(
             filetype: util::FileType::Yacrd,
             filename: input_path.to_string()
         }),
-        None | Some(util::FileType::YacrdOverlap) => {
+        Some(util::FileType::Sam) => bail!(error::Error::CantRunOperationOnFile {
+            operation: "split".to_string(),
+            filetype: util::FileType::Sam,
+            filename: input_path.to_string()
+        }),
+        Some(util::FileType::Bam) => bail!(error::Error::CantRunOperationOnFile {
+            operation: "split".to_string(),
+            filetype: util::FileType::Bam,
+            filename: input_path.to_string()
+        }),
+        Some(util::FileType::YacrdOverlap) => bail!(error::Error::CantRunOperationOnFile {
+            operation: "split".to_string(),
+            filetype: util::FileType::YacrdOverlap,
+            filename: input_path.to_string()
+        }),
+        None => {
             bail!(error::Error::UnableToDetectFileFormat {
                 filename: input_path.to_string()
             })
@@ -75,6 +96,7 @@ fn fasta<R, W>(
     output: W,
     badregions: &mut dyn stack::BadPart,
     not_covered: f64,
+    summary: &mut summary::Summary,
 ) -> Result<()>
 where
     R: std::io::Read,
@@ -88,30 +110,40 @@ where
             format: util::FileType::Fasta,
         })?;
 
-        let (badregion, length) = badregions
-            .get_bad_part(&unsafe { String::from_utf8_unchecked(record.name().to_vec()) })?;
+        let id = unsafe { String::from_utf8_unchecked(record.name().to_vec()) };
 
-        let rtype = editor::type_of_read(*length, badregion, not_covered);
+        let rtype = badregions.get_read_type(&id, not_covered)?;
+        let (badregion, length) = badregions.get_bad_part(&id)?;
+        summary.add_input(record.sequence().len(), *length != 0);
 
         if rtype == editor::ReadType::NotCovered {
             continue;
-        } else if rtype == editor::ReadType::NotBad {
+        } else if !rtype.is_bad() {
+            summary.add_output(record.sequence().len());
             writer
                 .write_record(&record)
                 .with_context(|| error::Error::WritingErrorNoFilename {
                     format: util::FileType::Fasta,
                 })?;
         } else {
+            /* for foldback read, sequence after fold is removed */
+            let mut end = *length as u32;
+            if rtype == editor::ReadType::Foldback {
+                if let Some(fold) = badregion.last() {
+                    end = fold.0;
+                }
+            }
+
             let mut poss = vec![0];
             for interval in badregion {
-                if interval.0 == 0 || interval.1 == *length as u32 {
+                if interval.0 == 0 || interval.1 >= end {
                     continue;
                 }
 
                 poss.push(interval.0);
                 poss.push(interval.1);
             }
-            poss.push(*length as u32);
+            poss.push(end);
 
             for pos in poss.chunks(2) {
                 if pos[0] as usize > record.sequence().len()
@@ -121,6 +153,7 @@ where
                     break;
                 }
 
+                summary.add_output((pos[1] - pos[0]) as usize);
                 writer
                     .write_record(&noodles::fasta::Record::new(
                         noodles::fasta::record::Definition::new(
@@ -153,6 +186,7 @@ fn fastq<R, W>(
     output: W,
     badregions: &mut dyn stack::BadPart,
     not_covered: f64,
+    summary: &mut summary::Summary,
 ) -> Result<()>
 where
     R: std::io::Read,
@@ -166,18 +200,19 @@ where
             format: util::FileType::Fastq,
         })?;
 
-        let (badregion, length) = badregions.get_bad_part(
-            std::str::from_utf8(record.name())?
-                .split_ascii_whitespace()
-                .next()
-                .unwrap(),
-        )?;
+        let id = std::str::from_utf8(record.name())?
+            .split_ascii_whitespace()
+            .next()
+            .unwrap();
 
-        let rtype = editor::type_of_read(*length, badregion, not_covered);
+        let rtype = badregions.get_read_type(id, not_covered)?;
+        let (badregion, length) = badregions.get_bad_part(id)?;
+        summary.add_input(record.sequence().len(), *length != 0);
 
         if rtype == editor::ReadType::NotCovered {
             continue;
-        } else if rtype == editor::ReadType::NotBad {
+        } else if !rtype.is_bad() {
+            summary.add_output(record.sequence().len());
             writer
                 .write_record(&record)
                 .with_context(|| error::Error::WritingErrorNoFilename {
@@ -187,16 +222,24 @@ where
             let name = record.name();
             let description = record.description();
 
+            /* for foldback read, sequence after fold is removed */
+            let mut end = *length as u32;
+            if rtype == editor::ReadType::Foldback {
+                if let Some(fold) = badregion.last() {
+                    end = fold.0;
+                }
+            }
+
             let mut poss = vec![0];
             for interval in badregion {
-                if interval.0 == 0 || interval.1 == *length as u32 {
+                if interval.0 == 0 || interval.1 >= end {
                     continue;
                 }
 
                 poss.push(interval.0);
                 poss.push(interval.1);
             }
-            poss.push(*length as u32);
+            poss.push(end);
 
             for pos in poss.chunks(2) {
                 if pos[0] as usize > record.sequence().len()
@@ -206,6 +249,7 @@ where
                     break;
                 }
 
+                summary.add_output((pos[1] - pos[0]) as usize);
                 writer
                     .write_record(&noodles::fastq::Record::new(
                         noodles::fastq::record::Definition::new(
@@ -226,6 +270,7 @@ where
 }
 
 #[cfg(test)]
+#[allow(clippy::redundant_static_lifetimes, clippy::unused_unit)]
 mod tests {
     use super::*;
 
@@ -265,9 +310,13 @@ ACTG
         stack.compute_all_bad_part();
 
         let mut output: Vec<u8> = Vec::new();
-        fasta(FASTA_FILE, &mut output, &mut stack, 0.8).unwrap();
+        let mut summary = summary::Summary::new();
+        fasta(FASTA_FILE, &mut output, &mut stack, 0.8, &mut summary).unwrap();
 
         assert_eq!(FASTA_FILE_SPLITED, &output[..]);
+        assert_eq!(vec![22, 4, 4], summary.before);
+        assert_eq!(vec![13, 4, 4, 4], summary.after);
+        assert_eq!(2, summary.no_overlap);
     }
 
     const FASTQ_FILE: &'static [u8] = b"@1
@@ -315,8 +364,43 @@ ACTG
         stack.compute_all_bad_part();
 
         let mut output: Vec<u8> = Vec::new();
-        fastq(FASTQ_FILE, &mut output, &mut stack, 0.8).unwrap();
+        fastq(
+            FASTQ_FILE,
+            &mut output,
+            &mut stack,
+            0.8,
+            &mut summary::Summary::new(),
+        )
+        .unwrap();
 
         assert_eq!(FASTQ_FILE_FILTRED, &output[..]);
     }
+
+    #[test]
+    fn foldback() {
+        let report: &[u8] = b"Foldback\t1\t22\t2,0,2;11,11,22
+";
+        let mut stack = stack::FromReport::from_reader(Box::new(report), "report").unwrap();
+
+        let mut output: Vec<u8> = Vec::new();
+        fasta(
+            FASTA_FILE,
+            &mut output,
+            &mut stack,
+            0.8,
+            &mut summary::Summary::new(),
+        )
+        .unwrap();
+
+        assert_eq!(
+            b">1_0_11
+ACTGGGGGGAC
+>2
+ACTG
+>3
+ACTG
+",
+            &output[..]
+        );
+    }
 }
@@ -20,73 +20,182 @@ OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
 SOFTWARE.
  */
 
+//! Error type of yacrd
+
 /* crate use */
 use thiserror::Error;
 
 /* local use */
 use crate::util;
 
+/// Errors raised by yacrd
 #[derive(Debug, Error)]
 pub enum Error {
+    /// File can't be opened for reading
     #[error(
         "Reading of the file '{filename:}' impossible, does it exist and can be read by the user?"
     )]
-    CantReadFile { filename: String },
+    CantReadFile {
+        /// path of the file
+        filename: String,
+    },
 
+    /// File can't be created or opened for writing
     #[error("Creation/opening of the file '{filename:}' impossible, directory in path exist? can be written by the user?")]
-    CantWriteFile { filename: String },
+    CantWriteFile {
+        /// path of the file
+        filename: String,
+    },
+
+    /// Standard input or standard output is used by two files
+    #[error("Standard {stream} can't be used by {first} and {second} at same time")]
+    StandardStreamUsedTwice {
+        /// name of stream (input or output)
+        stream: String,
+        /// first option
+        first: String,
+        /// second option
+        second: String,
+    },
 
-    #[error("Format detection for '{filename:}' file not possible, filename need to contains .fasta, .fa, .fastq, fq, .paf, .m4, .mhap or .yacrd")]
-    UnableToDetectFileFormat { filename: String },
+    /// Format of file can't be detected
+    #[error("Format detection for '{filename:}' file not possible, content isn't recognized and filename need to contains .fasta, .fa, .fastq, fq, .paf, .m4, .mhap, .sam, .bam, .yovl or .yacrd, you can also set format with --input-format")]
+    UnableToDetectFileFormat {
+        /// path of the file
+        filename: String,
+    },
 
+    /// Operation isn't available for this type of file
     #[error(
         "This operation {operation:} can't be run on this type ({filetype:?}) of file {filename:}"
     )]
     CantRunOperationOnFile {
+        /// name of the operation
         operation: String,
+        /// type of the file
         filetype: util::FileType,
+        /// path of the file
         filename: String,
     },
 
+    /// Error during parsing of a file
     #[error("Error durring reading of file {filename:} in format {format:?}")]
     Reading {
+        /// path of the file
         filename: String,
+        /// format of the file
         format: util::FileType,
     },
 
+    /// Error during parsing of a stream
     #[error("Error during reading a file in format {format:?}")]
-    ReadingErrorNoFilename { format: util::FileType },
+    ReadingErrorNoFilename {
+        /// format of the stream
+        format: util::FileType,
+    },
 
+    /// Error during writing of a stream
     #[error("Error during writing of file in format {format:?}")]
-    WritingErrorNoFilename { format: util::FileType },
+    WritingErrorNoFilename {
+        /// format of the stream
+        format: util::FileType,
+    },
 
+    /// Directory of on disk overlap storage can't be created
     #[error("Error during yacrd overlap path creation {path:?}")]
-    PathCreation { path: std::path::PathBuf },
+    PathCreation {
+        /// path of the directory
+        path: std::path::PathBuf,
+    },
 
+    /// Directory of on disk overlap storage can't be removed
     #[error("Error during yacrd overlap path destruction {path:?}")]
-    PathDestruction { path: std::path::PathBuf },
+    PathDestruction {
+        /// path of the directory
+        path: std::path::PathBuf,
+    },
 
+    /// Yacrd reach a part of code that should never be reached
     #[error("If you get this error please contact the author with this message and command line you use: {name:?}")]
-    NotReachableCode { name: String },
+    NotReachableCode {
+        /// location in code
+        name: String,
+    },
 
+    /// A bad region of a yacrd report can't be parsed
     #[error("Yacrd postion seems corrupt")]
     CorruptYacrdReportInPosition,
 
+    /// A line of a yacrd report can't be parsed
     #[error("Your yacrd file {name} seems corrupt at line {line} you probably need to relaunch analisys with overlapping file")]
-    CorruptYacrdReport { name: String, line: usize },
+    CorruptYacrdReport {
+        /// path of the report
+        name: String,
+        /// line number
+        line: usize,
+    },
+
+    /// A header line of a yacrd report can't be parsed
+    #[error("Header line '{line}' of yacrd file {name} seems corrupt")]
+    CorruptYacrdReportHeader {
+        /// path of the report
+        name: String,
+        /// content of the line
+        line: String,
+    },
+
+    /// A CIGAR string can't be parsed
+    #[error("CIGAR string {cigar} seems corrupt")]
+    CorruptCigar {
+        /// CIGAR string
+        cigar: String,
+    },
+
+    /// Length of an alignment target isn't present in header
+    #[error("Reference {name} isn't present in alignment header, length of this read is unknown")]
+    MissingReferenceLength {
+        /// name of reference
+        name: String,
+    },
+
+    /// In streaming mode, overlaps of a read aren't contiguous
+    #[error("Overlaps of read {name} aren't contiguous, streaming mode requires overlaps grouped by query read")]
+    ReadNotContiguous {
+        /// name of read
+        name: String,
+    },
+
+    /// Streaming mode can't be used with this operation
+    #[error("Streaming mode can't be used with {operation}, bad regions of reads aren't kept")]
+    StreamingNotCompatible {
+        /// name of the operation
+        operation: String,
+    },
+
+    /// Ondisk mode can't be used with this operation
+    #[error("Ondisk mode can't be used with {operation}, all overlaps would be loaded in memory")]
+    OnDiskNotCompatible {
+        /// name of the operation
+        operation: String,
+    },
 
+    /// On disk database can't be opened
     #[error("Error durring open database")]
     OnDiskOpen,
 
+    /// On disk database can't be read
     #[error("Error durring read database")]
     OnDiskReadDatabase,
 
+    /// Overlaps read from on disk database can't be deserialized
     #[error("Error durring on disk deserialize vector")]
     OnDiskDeserializeVec,
 
+    /// Overlaps can't be serialized in on disk database
     #[error("Error durring on disk serialize vector")]
     OnDiskSerializeVec,
 
+    /// Write in on disk database failed
     #[error("Error durring on disk batch application")]
     OnDiskBatchApplication,
 }
@@ -0,0 +1,357 @@
+/*
+Copyright (c) 2019 Pierre Marijon <pmarijon@mpi-inf.mpg.de>
+
+Permission is hereby granted, free of charge, to any person obtaining a copy
+of this software and associated documentation files (the "Software"), to deal
+in the Software without restriction, including without limitation the rights
+to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+copies of the Software, and to permit persons to whom the Software is
+furnished to do so, subject to the following conditions:
+
+The above copyright notice and this permission notice shall be included in all
+copies or substantial portions of the Software.
+
+THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+SOFTWARE.
+ */
+
+//! Provenance header of yacrd report: format version, yacrd version, parameters and inputs
+
+/* std use */
+use std::io::BufRead;
+
+/* crate use */
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+
+/* local use */
+use crate::error;
+
+/// Version of report format written by this yacrd, headerless reports are version 1
+pub const REPORT_VERSION: u32 = 2;
+
+/// An input file of a report with crc32 of its content
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Input {
+    /// path of the file
+    pub path: String,
+    /// crc32 of raw file content, None if it isn't known when header is written, it's then written
+    /// in a trailing input line, see [ReportHeader::write_input]
+    pub crc32: Option<u32>,
+}
+
+/// Provenance header of a report, written in lines starting with `# `, or in a first
+/// `{"yacrd_header": …}` object in jsonl report
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ReportHeader {
+    /// version of report format
+    pub format: u32,
+    /// version of yacrd
+    pub version: String,
+    /// command line
+    pub command: Option<String>,
+    /// name and value of parameters
+    pub params: Vec<(String, String)>,
+    /// input files
+    pub inputs: Vec<Input>,
+    /// other header lines, like information on detection
+    pub comments: Vec<String>,
+}
+
+impl Default for ReportHeader {
+    fn default() -> Self {
+        ReportHeader::new()
+    }
+}
+
+impl ReportHeader {
+    /// Header of current report format and yacrd version, without parameters and inputs
+    pub fn new() -> Self {
+        ReportHeader {
+            format: REPORT_VERSION,
+            version: env!("CARGO_PKG_VERSION").to_string(),
+            command: None,
+            params: Vec::new(),
+            inputs: Vec::new(),
+            comments: Vec::new(),
+        }
+    }
+
+    /// Add a parameter
+    pub fn add_param<T: std::fmt::Display>(&mut self, name: &str, value: T) {
+        self.params.push((name.to_string(), value.to_string()));
+    }
+
+    /// Get value of a parameter
+    pub fn get_param(&self, name: &str) -> Option<&str> {
+        self.params
+            .iter()
+            .find(|(key, _)| key == name)
+            .map(|(_, value)| value.as_str())
+    }
+
+    /// Get parameters with a value different from `params`, as (name, header value, other value)
+    pub fn mismatch<'a>(
+        &'a self,
+        params: &'a [(String, String)],
+    ) -> Vec<(&'a str, &'a str, &'a str)> {
+        params
+            .iter()
+            .filter_map(|(name, value)| match self.get_param(name) {
+                Some(old) if old != value => Some((name.as_str(), old, value.as_str())),
+                _ => None,
+            })
+            .collect()
+    }
+
+    /// Write header lines
+    pub fn write<W: std::io::Write>(&self, out: &mut W) -> std::io::Result<()> {
+        writeln!(out, "# format: yacrd-report {}", self.format)?;
+        writeln!(out, "# version: {}", self.version)?;
+        if let Some(command) = &self.command {
+            writeln!(out, "# command: {}", command)?;
+        }
+        for (name, value) in self.params.iter() {
+            writeln!(out, "# param: {}={}", name, value)?;
+        }
+        for input in self.inputs.iter() {
+            ReportHeader::write_input(input, out)?;
+        }
+        for comment in self.comments.iter() {
+            writeln!(out, "# {}", comment)?;
+        }
+
+        Ok(())
+    }
+
+    /// Write header as a json object in one line
+    pub fn write_jsonl<W: std::io::Write>(&self, out: &mut W) -> std::io::Result<()> {
+        serde_json::to_writer(&mut *out, &JsonHeader { yacrd_header: self })?;
+        writeln!(out)
+    }
+
+    /// Write a trailing input line, at end of a report whose header was written before input was
+    /// read. When report is read, crc32 of this line completes input of header with the same path.
+    pub fn write_input<W: std::io::Write>(input: &Input, out: &mut W) -> std::io::Result<()> {
+        match input.crc32 {
+            Some(crc32) => writeln!(out, "# input: {} crc32:{:08x}", input.path, crc32),
+            None => writeln!(out, "# input: {}", input.path),
+        }
+    }
+
+    /// Write a trailing input line as a json object in one line, see [ReportHeader::write_input]
+    pub fn write_input_jsonl<W: std::io::Write>(input: &Input, out: &mut W) -> std::io::Result<()> {
+        serde_json::to_writer(&mut *out, &JsonInput { yacrd_input: input })?;
+        writeln!(out)
+    }
+
+    /// Add an input, if input has a crc32 and an input with the same path and without crc32 is
+    /// present, its crc32 is set
+    pub fn add_input(&mut self, input: Input) {
+        match self
+            .inputs
+            .iter_mut()
+            .find(|x| input.crc32.is_some() && x.path == input.path && x.crc32.is_none())
+        {
+            Some(known) => known.crc32 = input.crc32,
+            None => self.inputs.push(input),
+        }
+    }
+
+    /// Read header lines at begin of a report, None if report has no format line (legacy report)
+    pub fn read(input: &mut dyn BufRead, filename: &str) -> Result<Option<Self>> {
+        let mut header = ReportHeader::new();
+        let mut format = None;
+        let mut line = String::new();
+
+        while input
+            .fill_buf()
+            .with_context(|| error::Error::CantReadFile {
+                filename: filename.to_string(),
+            })?
+            .first()
+            == Some(&b'#')
+        {
+            line.clear();
+            input
+                .read_line(&mut line)
+                .with_context(|| error::Error::CantReadFile {
+                    filename: filename.to_string(),
+                })?;
+
+            if let Some(value) = header.read_line(&line, filename)? {
+                format = Some(value);
+            }
+        }
+
+        Ok(format.map(|format| {
+            header.format = format;
+            header
+        }))
+    }
+
+    /// Read a header line, trailing input lines are also accepted, return version of report
+    /// format if it's a format line
+    pub fn read_line(&mut self, line: &str, filename: &str) -> Result<Option<u32>> {
+        let content = line.trim_end_matches(['\n', '\r']);
+        let content = content.strip_prefix('#').unwrap_or(content);
+        let content = content.strip_prefix(' ').unwrap_or(content);
+
+        let corrupt = || error::Error::CorruptYacrdReportHeader {
+            name: filename.to_string(),
+            line: content.to_string(),
+        };
+
+        match content.split_once(": ") {
+            Some(("format", value)) => {
+                return Ok(Some(
+                    value
+                        .strip_prefix("yacrd-report ")
+                        .and_then(|v| v.parse::<u32>().ok())
+                        .with_context(corrupt)?,
+                ))
+            }
+            Some(("version", value)) => self.version = value.to_string(),
+            Some(("command", value)) => self.command = Some(value.to_string()),
+            Some(("param", value)) => {
+                let (name, value) = value.split_once('=').with_context(corrupt)?;
+                self.add_param(name, value);
+            }
+            Some(("input", value)) => {
+                let input = match value.rsplit_once(" crc32:") {
+                    Some((path, crc32)) => Input {
+                        path: path.to_string(),
+                        crc32: Some(u32::from_str_radix(crc32, 16).ok().with_context(corrupt)?),
+                    },
+                    None => Input {
+                        path: value.to_string(),
+                        crc32: None,
+                    },
+                };
+                self.add_input(input);
+            }
+            _ => self.comments.push(content.to_string()),
+        }
+
+        Ok(None)
+    }
+}
+
+#[derive(Serialize)]
+struct JsonHeader<'a> {
+    yacrd_header: &'a ReportHeader,
+}
+
+#[derive(Serialize)]
+struct JsonInput<'a> {
+    yacrd_input: &'a Input,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn write_read() {
+        let mut header = ReportHeader::new();
+        header.command = Some("yacrd -i reads.paf -o reads.yacrd -c 4".to_string());
+        header.add_param("coverage", 4);
+        header.add_param("not-coverage", 0.8);
+        header.inputs.push(Input {
+            path: "reads with space.paf".to_string(),
+            crc32: Some(0x1a2b3c),
+        });
+        header.inputs.push(Input {
+            path: "-".to_string(),
+            crc32: None,
+        });
+        header.comments.push("coverage threshold: 4".to_string());
+
+        let mut out = Vec::new();
+        header.write(&mut out).unwrap();
+
+        assert_eq!(
+            format!(
+                "# format: yacrd-report 2
+# version: {}
+# command: yacrd -i reads.paf -o reads.yacrd -c 4
+# param: coverage=4
+# param: not-coverage=0.8
+# input: reads with space.paf crc32:001a2b3c
+# input: -
+# coverage threshold: 4
+",
+                env!("CARGO_PKG_VERSION")
+            ),
+            String::from_utf8(out.clone()).unwrap()
+        );
+
+        out.extend(b"NotBad\tA\t1000\t\n");
+        let mut input: &[u8] = &out;
+        assert_eq!(
+            Some(header),
+            ReportHeader::read(&mut input, "report").unwrap()
+        );
+        assert_eq!(b"NotBad\tA\t1000\t\n", input);
+    }
+
+    #[test]
+    fn trailing_input() {
+        let mut header = ReportHeader::new();
+        header.inputs.push(Input {
+            path: "-".to_string(),
+            crc32: None,
+        });
+
+        let mut out = Vec::new();
+        header.write(&mut out).unwrap();
+        let mut input: &[u8] = &out;
+        let mut read = ReportHeader::read(&mut input, "report").unwrap().unwrap();
+        assert_eq!(None, read.inputs[0].crc32);
+
+        header.inputs[0].crc32 = Some(0x1a2b3c);
+        let mut trailing = Vec::new();
+        ReportHeader::write_input(&header.inputs[0], &mut trailing).unwrap();
+        assert_eq!(b"# input: - crc32:001a2b3c\n", trailing.as_slice());
+
+        read.read_line(std::str::from_utf8(&trailing).unwrap(), "report")
+            .unwrap();
+        assert_eq!(header, read);
+    }
+
+    #[test]
+    fn legacy() {
+        let mut input: &[u8] = b"NotBad\tA\t1000\t\n";
+        assert_eq!(None, ReportHeader::read(&mut input, "report").unwrap());
+        assert_eq!(b"NotBad\tA\t1000\t\n", input);
+
+        let mut input: &[u8] = b"# coverage threshold: 4\nNotBad\tA\t1000\t\n";
+        assert_eq!(None, ReportHeader::read(&mut input, "report").unwrap());
+
+        let mut input: &[u8] = b"# format: yacrd-report two\n";
+        assert!(ReportHeader::read(&mut input, "report").is_err());
+    }
+
+    #[test]
+    fn mismatch() {
+        let mut header = ReportHeader::new();
+        header.add_param("coverage", 4);
+        header.add_param("not-coverage", 0.8);
+
+        let params = vec![
+            ("not-coverage".to_string(), "0.5".to_string()),
+            ("coverage".to_string(), "4".to_string()),
+            ("other".to_string(), "1".to_string()),
+        ];
+
+        assert_eq!(
+            vec![("not-coverage", "0.8", "0.5")],
+            header.mismatch(&params)
+        );
+    }
+}
@@ -20,31 +20,246 @@ OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
 SOFTWARE.
 */
 
+/* crate use */
+use anyhow::{bail, Result};
+
+/* local use */
+use crate::error;
+
 #[derive(Debug, Clone, serde::Deserialize)]
 pub struct PafRecord<'a> {
     pub read_a: &'a str,
     pub length_a: usize,
     pub begin_a: u32,
     pub end_a: u32,
-    pub _strand: char,
+    pub strand: char,
     pub read_b: &'a str,
     pub length_b: usize,
     pub begin_b: u32,
     pub end_b: u32,
+    pub nb_match: u64,
+    pub block_length: u64,
+    pub mapq: u8,
+}
+
+impl<'a> PafRecord<'a> {
+    /// Number of matches divided by alignment block length
+    pub fn identity(&self) -> f64 {
+        if self.block_length == 0 {
+            0.0
+        } else {
+            self.nb_match as f64 / self.block_length as f64
+        }
+    }
 }
 
 #[derive(Debug, Clone, serde::Deserialize)]
 pub struct M4Record<'a> {
     pub read_a: &'a str,
     pub read_b: &'a str,
-    pub _error: f64,
+    pub error: f64,
     pub _shared_min: u64,
-    pub _strand_a: char,
+    pub strand_a: char,
     pub begin_a: u32,
     pub end_a: u32,
     pub length_a: usize,
-    pub _strand_b: char,
+    pub strand_b: char,
     pub begin_b: u32,
     pub end_b: u32,
     pub length_b: usize,
 }
+
+impl<'a> M4Record<'a> {
+    /// Identity of overlap, error can be a fraction or a percentage
+    pub fn identity(&self) -> f64 {
+        if self.error > 1.0 {
+            1.0 - self.error / 100.0
+        } else {
+            1.0 - self.error
+        }
+    }
+
+    /// Length of the longest overlap interval
+    pub fn block_length(&self) -> u64 {
+        (self.end_a - self.begin_a).max(self.end_b - self.begin_b) as u64
+    }
+}
+
+const CIGAR_OPS: &[u8; 9] = b"MIDNSHP=X";
+
+/// Compute query length, query interval and target interval of an alignment from its CIGAR.
+///
+/// Query interval is given on the forward strand of query, clipped bases (soft or hard) are included in query length.
+pub fn cigar2intervals<I>(ops: I, reverse: bool, begin_b: u32) -> (usize, (u32, u32), (u32, u32))
+where
+    I: Iterator<Item = (u8, u32)>,
+{
+    let mut before = 0;
+    let mut aligned = 0;
+    let mut after = 0;
+    let mut target = 0;
+
+    for (op, len) in ops {
+        match op {
+            b'S' | b'H' => {
+                if aligned == 0 && target == 0 {
+                    before += len;
+                } else {
+                    after += len;
+                }
+            }
+            b'M' | b'=' | b'X' => {
+                aligned += len;
+                target += len;
+            }
+            b'I' => aligned += len,
+            b'D' | b'N' => target += len,
+            _ => (),
+        }
+    }
+
+    let length = before + aligned + after;
+    let query = if reverse {
+        (after, after + aligned)
+    } else {
+        (before, before + aligned)
+    };
+
+    (length as usize, query, (begin_b, begin_b + target))
+}
+
+/// Split an alignment on insertions or deletions larger than `max_indel`, return query and target interval of each part.
+///
+/// Like in PAF and SAM, CIGAR of a reverse alignment is given on reverse complement of query.
+pub fn split_on_indel<I>(
+    ops: I,
+    query: (u32, u32),
+    target: (u32, u32),
+    reverse: bool,
+    max_indel: u32,
+) -> Vec<((u32, u32), (u32, u32))>
+where
+    I: Iterator<Item = (u8, u32)>,
+{
+    let mut offsets = Vec::new();
+    let (mut q, mut t) = (0, 0);
+    let (mut begin_q, mut begin_t) = (0, 0);
+
+    for (op, len) in ops {
+        match op {
+            b'M' | b'=' | b'X' => {
+                q += len;
+                t += len;
+            }
+            b'I' | b'D' | b'N' if len > max_indel => {
+                offsets.push(((begin_q, q), (begin_t, t)));
+
+                if op == b'I' {
+                    q += len;
+                } else {
+                    t += len;
+                }
+
+                begin_q = q;
+                begin_t = t;
+            }
+            b'I' => q += len,
+            b'D' | b'N' => t += len,
+            _ => (),
+        }
+    }
+    offsets.push(((begin_q, q), (begin_t, t)));
+
+    offsets
+        .into_iter()
+        .filter(|(q, t)| q.0 != q.1 && t.0 != t.1)
+        .map(|(q, t)| {
+            let ovl_a = if reverse {
+                (
+                    query.1.saturating_sub(q.1).max(query.0),
+                    query.1.saturating_sub(q.0),
+                )
+            } else {
+                (query.0 + q.0, (query.0 + q.1).min(query.1))
+            };
+
+            (ovl_a, (target.0 + t.0, (target.0 + t.1).min(target.1)))
+        })
+        .collect()
+}
+
+/// Get value of a SAM-style tag (for example `cg:Z:`) in optional fields of a PAF record
+pub fn paf_tag<'a>(record: &'a csv::StringRecord, tag: &str) -> Option<&'a str> {
+    record.iter().skip(12).find_map(|x| x.strip_prefix(tag))
+}
+
+/// Parse a CIGAR string in (operation, length) pairs
+pub fn parse_cigar(cigar: &str) -> Result<Vec<(u8, u32)>> {
+    let mut ops = Vec::new();
+    let mut len: u32 = 0;
+
+    for c in cigar.bytes() {
+        if c.is_ascii_digit() {
+            len = len * 10 + (c - b'0') as u32;
+        } else if CIGAR_OPS.contains(&c) {
+            ops.push((c, len));
+            len = 0;
+        } else {
+            bail!(error::Error::CorruptCigar {
+                cigar: cigar.to_string()
+            });
+        }
+    }
+
+    Ok(ops)
+}
+
+/// Convert CIGAR of a SAM or BAM record in (operation, length) pairs
+pub fn record_cigar(ops: &[noodles::sam::alignment::record::cigar::Op]) -> Vec<(u8, u32)> {
+    use noodles::sam::alignment::record::cigar::op::Kind;
+
+    ops.iter()
+        .map(|op| {
+            let code = match op.kind() {
+                Kind::Match => b'M',
+                Kind::Insertion => b'I',
+                Kind::Deletion => b'D',
+                Kind::Skip => b'N',
+                Kind::SoftClip => b'S',
+                Kind::HardClip => b'H',
+                Kind::Pad => b'P',
+                Kind::SequenceMatch => b'=',
+                Kind::SequenceMismatch => b'X',
+            };
+
+            (code, op.len() as u32)
+        })
+        .collect()
+}
+
+/// Compute alignment block length and identity of a SAM or BAM record, like columns 11 and 10 of
+/// paf. Matches are block length minus edit distance (`NM` tag), or number of `=` bases without
+/// edit distance. Without both, identity can't be computed and 1.0 is returned.
+pub fn cigar_identity(cigar: &[(u8, u32)], edit_distance: Option<u32>) -> (u64, f64) {
+    let block_length = cigar
+        .iter()
+        .filter(|(op, _)| b"MID=X".contains(op))
+        .map(|(_, len)| *len as u64)
+        .sum::<u64>();
+
+    let matches = match edit_distance {
+        Some(distance) => block_length.saturating_sub(distance as u64),
+        None if cigar.iter().any(|(op, _)| b"=X".contains(op)) => cigar
+            .iter()
+            .filter(|(op, _)| *op == b'=')
+            .map(|(_, len)| *len as u64)
+            .sum::<u64>(),
+        None => return (block_length, 1.0),
+    };
+
+    if block_length == 0 {
+        (0, 0.0)
+    } else {
+        (block_length, matches as f64 / block_length as f64)
+    }
+}
@@ -0,0 +1,81 @@
+/*
+Copyright (c) 2019 Pierre Marijon <pmarijon@mpi-inf.mpg.de>
+
+Permission is hereby granted, free of charge, to any person obtaining a copy
+of this software and associated documentation files (the "Software"), to deal
+in the Software without restriction, including without limitation the rights
+to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+copies of the Software, and to permit persons to whom the Software is
+furnished to do so, subject to the following conditions:
+
+The above copyright notice and this permission notice shall be included in all
+copies or substantial portions of the Software.
+
+THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+SOFTWARE.
+ */
+
+//! Yet Another Chimeric Read Detector for long reads.
+//!
+//! Using all-against-all read mapping, yacrd computes the pile-up coverage of each read and
+//! detects 'bad' regions, regions where coverage is lower or equal to a threshold. With this
+//! bad regions a read is classified as `Chimeric`, `NotCovered`, `Foldback`, `Repeat` or `NotBad`.
+//!
+//! The detection is split in two steps:
+//! - a [reads2ovl::Reads2Ovl] object collects overlap intervals of each read, in memory
+//!   ([reads2ovl::FullMemory]) or on disk ([reads2ovl::OnDisk])
+//! - a [stack::BadPart] object provides the bad regions of each read, computed from overlaps
+//!   ([stack::FromOverlap]) or read from a previous yacrd report ([stack::FromReport])
+//!
+//! Functions of [editor] use bad regions to classify reads, write report and filter, extract,
+//! split or scrubb sequence and overlap files. Reports start with a provenance header, see
+//! [header::ReportHeader].
+//!
+//! ```no_run
+//! use yacrd::reads2ovl::Reads2Ovl;
+//! use yacrd::stack::BadPart;
+//!
+//! # fn main() -> anyhow::Result<()> {
+//! let mut overlaps = yacrd::reads2ovl::FullMemory::new(8192);
+//! overlaps.init("reads.paf")?;
+//!
+//! let mut badparts = yacrd::stack::FromOverlap::new(Box::new(overlaps), 0);
+//! badparts.compute_all_bad_part();
+//!
+//! for read in badparts.get_reads() {
+//!     let (bads, length) = badparts.get_bad_part(&read)?;
+//!     let read_type = yacrd::editor::type_of_read(*length, bads, 0.8);
+//!
+//!     println!("{} {}", read, read_type.as_str());
+//! }
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! # Stability
+//!
+//! yacrd follows semantic versioning: [reads2ovl::Reads2Ovl], [stack::BadPart],
+//! [reads2ovl::FullMemory], [reads2ovl::OnDisk], [stack::FromOverlap], [stack::FromReport],
+//! [editor::type_of_read] and the other functions of [editor] don't change in an incompatible way
+//! without a new major version. Items hidden from this documentation are used by yacrd binary
+//! and aren't covered by this guarantee.
+
+#![warn(missing_docs)]
+
+/* mod declaration*/
+pub mod editor;
+pub mod error;
+pub mod header;
+mod io;
+pub mod reads2ovl;
+pub mod stack;
+pub mod summary;
+#[doc(hidden)]
+pub mod util;
+
+pub use util::FileType;
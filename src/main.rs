@@ -21,30 +21,116 @@ SOFTWARE.
  */
 
 /* crate use */
-use anyhow::{anyhow, Context, Result};
+use anyhow::{anyhow, bail, Context, Result};
 use clap::Parser;
 
+/* local use */
+use yacrd::editor;
+use yacrd::error;
+use yacrd::header;
+use yacrd::reads2ovl;
+use yacrd::reads2ovl::Reads2Ovl;
+use yacrd::stack;
+use yacrd::stack::BadPart;
+use yacrd::summary;
+use yacrd::util;
+
 /* mod declaration*/
 mod cli;
-mod editor;
-mod error;
-mod io;
-mod reads2ovl;
-mod stack;
-mod util;
 
 fn main() -> Result<()> {
-    env_logger::init();
+    env_logger::Builder::from_env(env_logger::Env::default().default_filter_or("warn")).init();
 
     let params = cli::Command::parse();
 
+    if let Some(subcmd) = &params.subcmd {
+        let (sub_input, sub_output) = match subcmd {
+            cli::SubCommand::Scrubb(s) => (Some(&s.input), &s.output),
+            cli::SubCommand::Filter(f) => (Some(&f.input), &f.output),
+            cli::SubCommand::Extract(e) => (Some(&e.input), &e.output),
+            cli::SubCommand::Split(s) => (Some(&s.input), &s.output),
+            cli::SubCommand::Mask(m) => (Some(&m.input), &m.output),
+            cli::SubCommand::Convert(c) => (None, &c.output),
+        };
+
+        if params.input == util::STD_STREAM
+            && sub_input.map(|x| x.as_str()) == Some(util::STD_STREAM)
+        {
+            bail!(error::Error::StandardStreamUsedTwice {
+                stream: "input".to_string(),
+                first: "overlap input".to_string(),
+                second: "subcommand input".to_string(),
+            });
+        }
+
+        if params.output == util::STD_STREAM && sub_output == util::STD_STREAM {
+            bail!(error::Error::StandardStreamUsedTwice {
+                stream: "output".to_string(),
+                first: "report output".to_string(),
+                second: "subcommand output".to_string(),
+            });
+        }
+    }
+
+    if params.output == util::STD_STREAM && params.bedgraph.as_deref() == Some(util::STD_STREAM) {
+        bail!(error::Error::StandardStreamUsedTwice {
+            stream: "output".to_string(),
+            first: "report output".to_string(),
+            second: "bedgraph output".to_string(),
+        });
+    }
+
     /* Get bad region of reads */
+    let (mut input, compression, crc32) = util::read_file_crc32(&params.input, params.buffer_size)?;
+    let input_format = params
+        .input_format
+        .or_else(|| util::detect_file_type(&mut input, &params.input));
+
+    if params.streaming {
+        return streaming(input, input_format, compression, crc32, &params);
+    }
+
+    rayon::ThreadPoolBuilder::new()
+        .num_threads(params.threads.unwrap_or(1usize))
+        .build_global()?;
+
+    /* Detection parameters of report input are kept in provenance header */
+    let mut report_params = None;
     let mut reads2badregion: Box<dyn stack::BadPart> =
-        if Some(util::FileType::Yacrd) == util::get_file_type(&params.input) {
+        if Some(util::FileType::Yacrd) == input_format {
+            if let Some(cli::SubCommand::Convert(_)) = params.subcmd {
+                bail!(error::Error::CantRunOperationOnFile {
+                    operation: "convert".to_string(),
+                    filetype: util::FileType::Yacrd,
+                    filename: params.input.clone(),
+                });
+            }
+
+            if params.bedgraph.is_some() {
+                bail!(error::Error::CantRunOperationOnFile {
+                    operation: "bedgraph".to_string(),
+                    filetype: util::FileType::Yacrd,
+                    filename: params.input.clone(),
+                });
+            }
+
             /* Read bad part from yacrd report */
-            Box::new(stack::FromReport::new(&params.input)?)
+            let report = stack::FromReport::from_reader(input, &params.input)?;
+            report.check_params(
+                &[("not-coverage".to_string(), params.not_coverage.to_string())],
+                &params.input,
+            );
+            report_params = report.report_header().map(|h| h.params.clone());
+
+            Box::new(report)
         } else {
             /* Get bad part from overlap */
+            if params.ondisk.is_some() && params.coverage == stack::CoverageThreshold::Auto {
+                bail!(error::Error::OnDiskNotCompatible {
+                    operation: "coverage auto".to_string(),
+                });
+            }
+
             let mut reads2ovl: Box<dyn reads2ovl::Reads2Ovl> = match params.ondisk.clone() {
                 Some(on_disk_path) => Box::new(reads2ovl::OnDisk::new(
                     on_disk_path,
@@ -54,68 +140,145 @@ fn main() -> Result<()> {
                 None => Box::new(reads2ovl::FullMemory::new(params.buffer_size)),
             };
 
-            reads2ovl.init(&params.input)?;
+            if let Some(filter) = reads2ovl.overlap_filter() {
+                *filter = overlap_filter(&params);
+            }
+            if params.foldback || params.partner_junction {
+                reads2ovl.keep_details();
+            }
+            reads2ovl.init_from_reader(input, &params.input, input_format)?;
+
+            if let Some(cli::SubCommand::Convert(c)) = &params.subcmd {
+                let mut out = util::write_file(
+                    &c.output,
+                    niffler::compression::Format::No,
+                    params.buffer_size,
+                )?;
+
+                reads2ovl
+                    .write_yovl(&mut out)
+                    .with_context(|| anyhow!("Filename: {}", &c.output))?;
+            }
 
-            Box::new(stack::FromOverlap::new(reads2ovl, params.coverage))
+            let mut from_overlap =
+                stack::FromOverlap::with_params(reads2ovl, detection_params(&params));
+            if let Some(out) = bedgraph_output(&params, compression)? {
+                from_overlap.write_coverage_profile(out);
+            }
+            from_overlap.compute_all_bad_part();
+            from_overlap.finish().with_context(|| {
+                anyhow!("Filename: {}", params.bedgraph.as_deref().unwrap_or(""))
+            })?;
+
+            Box::new(from_overlap)
         };
 
     /* Write report */
-    let raw_out = Box::new(std::io::BufWriter::new(
-        std::fs::File::create(&params.output).with_context(|| error::Error::CantWriteFile {
-            filename: params.output.clone(),
-        })?,
-    ));
-
-    let mut out = niffler::get_writer(
-        raw_out,
+    let mut out = util::write_file(
+        &params.output,
         niffler::compression::Format::No,
-        niffler::compression::Level::One,
+        params.buffer_size,
     )?;
 
-    rayon::ThreadPoolBuilder::new()
-        .num_threads(params.threads.unwrap_or(1usize))
-        .build_global()?;
-    reads2badregion.compute_all_bad_part();
+    /* input is completely read, its crc32 is known */
+    let mut provenance = report_header(&params);
+    provenance.inputs[0].crc32 = Some(crc32.borrow().clone().finalize());
+    if let Some(report_params) = report_params {
+        provenance.params = report_params
+            .into_iter()
+            .filter(|(name, _)| name != "not-coverage")
+            .collect();
+        provenance.add_param("not-coverage", params.not_coverage);
+    }
+    provenance.comments = reads2badregion.header();
+    write_header(&provenance, params.report_format, &mut out)
+        .with_context(|| anyhow!("Filename: {}", &params.output))?;
+
+    let report = params.report_format.writer();
+    let mut stats = summary::Summary::new();
+
+    /* reads are written in lexicographic order, report is the same across runs */
+    let mut reads: Vec<String> = reads2badregion.get_reads().into_iter().collect();
+    reads.sort_unstable();
 
-    for read in reads2badregion.get_reads() {
+    for read in reads {
+        let rtype = reads2badregion.get_read_type(&read, params.not_coverage)?;
+        let repeats = reads2badregion.get_repeat_part(&read).map(<[_]>::to_vec);
+        let junctions = reads2badregion.get_junctions(&read).map(<[_]>::to_vec);
+        let read_stats = reads2badregion.get_read_stats(&read);
         let (bads, len) = reads2badregion.get_bad_part(&read)?;
-        editor::report(&read, *len, bads, params.not_coverage, &mut out)
+        stats.add_read(&rtype, *len, bads);
+
+        let annotations = editor::ReadAnnotations {
+            junctions: junctions.as_deref(),
+            repeats: repeats.as_deref(),
+            stats: read_stats,
+        };
+        report(&read, *len, bads, &annotations, &rtype, &mut out)
             .with_context(|| anyhow!("Filename: {}", &params.output))?;
     }
 
     /* Run post operation on read or overlap */
-    match params.subcmd {
+    let mut score_filter;
+    let badregions: &mut dyn stack::BadPart = match min_junction_score(&params) {
+        Some(min_score) => {
+            score_filter = stack::ScoreFilter::new(&mut *reads2badregion, min_score);
+            &mut score_filter
+        }
+        None => &mut *reads2badregion,
+    };
+
+    match &params.subcmd {
         Some(cli::SubCommand::Scrubb(s)) => editor::scrubbing(
             &s.input,
             &s.output,
-            &mut *reads2badregion,
+            s.input_format,
+            badregions,
             params.not_coverage,
+            &mut stats,
             params.buffer_size,
         )?,
         Some(cli::SubCommand::Filter(f)) => editor::filter(
             &f.input,
             &f.output,
-            &mut *reads2badregion,
+            f.input_format,
+            badregions,
             params.not_coverage,
+            &mut stats,
             params.buffer_size,
         )?,
         Some(cli::SubCommand::Extract(e)) => editor::extract(
             &e.input,
             &e.output,
-            &mut *reads2badregion,
+            e.input_format,
+            badregions,
             params.not_coverage,
+            &mut stats,
             params.buffer_size,
         )?,
         Some(cli::SubCommand::Split(s)) => editor::split(
             &s.input,
             &s.output,
-            &mut *reads2badregion,
+            s.input_format,
+            badregions,
             params.not_coverage,
+            &mut stats,
+            params.buffer_size,
+        )?,
+        Some(cli::SubCommand::Mask(m)) => editor::mask(
+            &m.input,
+            &m.output,
+            m.input_format,
+            badregions,
+            m.hard,
+            &mut stats,
             params.buffer_size,
         )?,
-        None => (),
+        Some(cli::SubCommand::Convert(_)) | None => (),
     };
 
+    write_summary(&stats, &params)?;
+
     if let Some(on_disk_path) = params.ondisk {
         let path = std::path::PathBuf::from(on_disk_path);
         if path.is_dir() {
@@ -135,3 +298,278 @@ fn main() -> Result<()> {
 
     Ok(())
 }
+
+fn streaming(
+    input: Box<dyn std::io::BufRead>,
+    input_format: Option<util::FileType>,
+    compression: niffler::compression::Format,
+    crc32: util::SharedCrc32,
+    params: &cli::Command,
+) -> Result<()> {
+    if params.subcmd.is_some() {
+        bail!(error::Error::StreamingNotCompatible {
+            operation: "subcommand".to_string(),
+        });
+    }
+
+    if Some(util::FileType::Yacrd) == input_format {
+        bail!(error::Error::StreamingNotCompatible {
+            operation: "yacrd report input".to_string(),
+        });
+    }
+
+    if params.coverage == stack::CoverageThreshold::Auto {
+        bail!(error::Error::StreamingNotCompatible {
+            operation: "coverage auto".to_string(),
+        });
+    }
+    let detection = detection_params(params);
+
+    let out = std::rc::Rc::new(std::cell::RefCell::new(util::write_file(
+        &params.output,
+        niffler::compression::Format::No,
+        params.buffer_size,
+    )?));
+    let callback_out = out.clone();
+
+    /* header is written before input is read, crc32 of input is written in a trailing line */
+    let mut provenance = report_header(params);
+    write_header(&provenance, params.report_format, &mut *out.borrow_mut())
+        .with_context(|| anyhow!("Filename: {}", &params.output))?;
+
+    let report = params.report_format.writer::<Box<dyn std::io::Write>>();
+
+    let mut bedgraph = bedgraph_output(params, compression)?;
+    let stats = std::rc::Rc::new(std::cell::RefCell::new(summary::Summary::new()));
+    let callback_stats = stats.clone();
+
+    let not_coverage = params.not_coverage;
+    let output = params.output.clone();
+    let bedgraph_path = params.bedgraph.clone().unwrap_or_default();
+
+    let mut reads2ovl = reads2ovl::Streaming::new(
+        params.buffer_size,
+        Box::new(move |read, ovls, len| {
+            let parts = stack::FromOverlap::compute_parts(ovls, len, &detection);
+            let repeats = detection.repeat_coverage.map(|_| parts.repeats.as_slice());
+            let junctions = detection
+                .junction_annotation()
+                .then_some(parts.junctions.as_slice());
+
+            let rtype = editor::type_of_read(len, &parts.bads, not_coverage)
+                .with_repeat(repeats.is_some_and(|r| !r.is_empty()));
+            callback_stats
+                .borrow_mut()
+                .add_read(&rtype, len, &parts.bads);
+
+            let annotations = editor::ReadAnnotations {
+                junctions,
+                repeats,
+                stats: parts.stats,
+            };
+            report(
+                read,
+                len,
+                &parts.bads,
+                &annotations,
+                &rtype,
+                &mut callback_out.borrow_mut(),
+            )
+            .with_context(|| anyhow!("Filename: {}", &output))?;
+
+            if let Some(out) = bedgraph.as_mut() {
+                editor::bedgraph(read, &parts.profile, out)
+                    .with_context(|| anyhow!("Filename: {}", &bedgraph_path))?;
+            }
+
+            Ok(())
+        }),
+    );
+
+    if let Some(filter) = reads2ovl.overlap_filter() {
+        *filter = overlap_filter(params);
+    }
+    reads2ovl.init_from_reader(input, &params.input, input_format)?;
+
+    /* input is completely read, its crc32 is known */
+    provenance.inputs[0].crc32 = Some(crc32.borrow().clone().finalize());
+    let mut out = out.borrow_mut();
+    match params.report_format {
+        editor::ReportFormat::Yacrd | editor::ReportFormat::Bed => {
+            header::ReportHeader::write_input(&provenance.inputs[0], &mut *out)
+        }
+        editor::ReportFormat::Jsonl => {
+            header::ReportHeader::write_input_jsonl(&provenance.inputs[0], &mut *out)
+        }
+    }
+    .with_context(|| anyhow!("Filename: {}", &params.output))?;
+
+    let stats = stats.borrow();
+    write_summary(&stats, params)
+}
+
+fn write_summary(stats: &summary::Summary, params: &cli::Command) -> Result<()> {
+    if let Some(path) = &params.summary {
+        let mut out = util::write_file(path, niffler::compression::Format::No, params.buffer_size)?;
+        stats
+            .write(params.summary_format, &mut out)
+            .with_context(|| anyhow!("Filename: {}", path))?;
+    }
+
+    Ok(())
+}
+
+/* bedGraph is compressed like input, as subcommand outputs */
+fn bedgraph_output(
+    params: &cli::Command,
+    compression: niffler::compression::Format,
+) -> Result<Option<Box<dyn std::io::Write>>> {
+    match &params.bedgraph {
+        Some(path) => {
+            let mut out = util::write_file(path, compression, params.buffer_size)?;
+            writeln!(out, "track type=bedGraph name=yacrd_coverage")
+                .with_context(|| anyhow!("Filename: {}", path))?;
+
+            Ok(Some(out))
+        }
+        None => Ok(None),
+    }
+}
+
+fn write_header<W: std::io::Write>(
+    provenance: &header::ReportHeader,
+    format: editor::ReportFormat,
+    out: &mut W,
+) -> std::io::Result<()> {
+    match format {
+        editor::ReportFormat::Yacrd | editor::ReportFormat::Bed => provenance.write(out),
+        editor::ReportFormat::Jsonl => provenance.write_jsonl(out),
+    }
+}
+
+/// Options not recorded in report header, they change neither detection nor report content
+const UNRECORDED_OPTIONS: &[&str] = &[
+    "-t",
+    "--thread",
+    "-o",
+    "--output",
+    "--bedgraph",
+    "--summary",
+    "-d",
+    "--ondisk",
+];
+
+fn recorded_command<I>(args: I) -> String
+where
+    I: Iterator<Item = String>,
+{
+    let mut command = Vec::new();
+    let mut skip_value = false;
+
+    for arg in args {
+        if skip_value {
+            skip_value = false;
+        } else if UNRECORDED_OPTIONS.contains(&arg.as_str()) {
+            skip_value = true;
+        } else if !UNRECORDED_OPTIONS.iter().any(|option| {
+            if option.starts_with("--") {
+                arg.starts_with(&format!("{}=", option))
+            } else {
+                arg.starts_with(option) && !arg.starts_with("--")
+            }
+        }) {
+            command.push(arg);
+        }
+    }
+
+    command.join(" ")
+}
+
+fn report_header(params: &cli::Command) -> header::ReportHeader {
+    let mut provenance = header::ReportHeader::new();
+
+    provenance.command = Some(recorded_command(std::env::args()));
+
+    provenance.add_param("coverage", params.coverage);
+    if params.coverage == stack::CoverageThreshold::Auto {
+        provenance.add_param("auto-coverage-fraction", params.auto_coverage_fraction);
+    }
+    provenance.add_param("not-coverage", params.not_coverage);
+    if let Some(relative) = params.relative_coverage {
+        provenance.add_param("relative-coverage", relative);
+    }
+    if let Some(leave) = params.leave_coverage {
+        provenance.add_param("leave-coverage", leave);
+    }
+    provenance.add_param("min-bad-length", params.min_bad_length);
+    provenance.add_param("merge-distance", params.merge_distance);
+    if let Some(repeat) = params.repeat_coverage {
+        provenance.add_param("repeat-coverage", repeat);
+    }
+    provenance.add_param("foldback", params.foldback);
+    provenance.add_param("partner-junction", params.partner_junction);
+    provenance.add_param("junction-score", params.junction_score);
+    provenance.add_param("refine-junction", params.refine_junction);
+
+    provenance.inputs.push(header::Input {
+        path: params.input.clone(),
+        crc32: None,
+    });
+
+    provenance
+}
+
+fn min_junction_score(params: &cli::Command) -> Option<f64> {
+    match &params.subcmd {
+        Some(cli::SubCommand::Scrubb(s)) => s.min_junction_score,
+        Some(cli::SubCommand::Filter(f)) => f.min_junction_score,
+        Some(cli::SubCommand::Split(s)) => s.min_junction_score,
+        _ => None,
+    }
+}
+
+fn detection_params(params: &cli::Command) -> stack::DetectionParams {
+    let mut detection = match params.coverage {
+        stack::CoverageThreshold::Value(coverage) => stack::DetectionParams::new(coverage),
+        stack::CoverageThreshold::Auto => {
+            let mut detection = stack::DetectionParams::new(0);
+            detection.auto_coverage = Some(params.auto_coverage_fraction);
+            detection
+        }
+    };
+
+    detection.relative_coverage = params.relative_coverage;
+    detection.leave_coverage = params.leave_coverage;
+    detection.min_length = params.min_bad_length;
+    detection.merge_distance = params.merge_distance;
+    detection.repeat_coverage = params.repeat_coverage;
+    detection.foldback = params.foldback;
+    detection.partner_junction = params.partner_junction;
+    detection.junction_score = params.junction_score || min_junction_score(params).is_some();
+    detection.refine_junction = params.refine_junction;
+    detection.coverage_profile = params.bedgraph.is_some();
+    detection.read_stats = params.report_format == editor::ReportFormat::Jsonl;
+
+    detection
+}
+
+fn overlap_filter(params: &cli::Command) -> reads2ovl::OverlapFilter {
+    let mut filter = reads2ovl::OverlapFilter::new(
+        params.min_mapq,
+        params.min_block_length,
+        params.min_identity,
+    );
+
+    filter.keep_self = params.keep_self_overlap;
+    filter.remove_duplicate = params.remove_duplicate;
+    filter.classes = params.overlap_class.clone();
+    filter.max_hang = params.max_overhang;
+    filter.int_frac = params.internal_fraction;
+    filter.split_indel = params.split_indel;
+    filter.trim = params.trim_overlap;
+    filter.trim_fraction = params.trim_overlap_fraction;
+    filter.alignment_types = params.alignment_type.clone();
+    filter.tag_conditions = params.tag_filter.clone();
+
+    filter
+}
@@ -0,0 +1,707 @@
+/*
+   Copyright (c) 2019 Pierre Marijon <pmarijon@mpi-inf.mpg.de>
+
+   Permission is hereby granted, free of charge, to any person obtaining a copy
+   of this software and associated documentation files (the "Software"), to deal
+   in the Software without restriction, including without limitation the rights
+   to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+   copies of the Software, and to permit persons to whom the Software is
+   furnished to do so, subject to the following conditions:
+
+   The above copyright notice and this permission notice shall be included in all
+   copies or substantial portions of the Software.
+
+   THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+   IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+   FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+   AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+   LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+   OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+   SOFTWARE.
+*/
+
+//! Filter and classify overlaps before coverage computation
+
+/* crate use */
+use log::warn;
+
+/* local use */
+use crate::reads2ovl;
+
+type PairIntervals = ((u32, u32), (u32, u32));
+
+/// Mapping quality value used when mapping quality isn't available
+pub const MAPQ_UNAVAILABLE: u8 = 255;
+
+/// Classification of an overlap with overhangs of both reads, like miniasm
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OverlapClass {
+    /// one read is contained in the other
+    Containment,
+    /// end of one read overlaps begin of the other
+    Dovetail,
+    /// both reads keep long unaligned overhangs, typically a repeat
+    Internal,
+}
+
+impl OverlapClass {
+    /// Classify an overlap, it's internal if overhang of one side is larger than `max_hang` or if aligned length is smaller than `int_frac` of aligned length plus overhangs
+    pub fn classify(pair: &reads2ovl::OverlapPair, max_hang: u32, int_frac: f64) -> Self {
+        let (begin_a, end_a) = pair.ovl_a;
+        let len_a = pair.len_a as u32;
+        let len_b = pair.len_b as u32;
+
+        /* put interval of read b on the strand of read a */
+        let (begin_b, end_b) = if pair.reverse {
+            (
+                len_b.saturating_sub(pair.ovl_b.1),
+                len_b.saturating_sub(pair.ovl_b.0),
+            )
+        } else {
+            pair.ovl_b
+        };
+
+        let left_a = begin_a;
+        let left_b = begin_b;
+        let right_a = len_a.saturating_sub(end_a);
+        let right_b = len_b.saturating_sub(end_b);
+
+        let ext5 = left_a.min(left_b);
+        let ext3 = right_a.min(right_b);
+        let aligned = end_a.saturating_sub(begin_a);
+
+        if ext5 > max_hang
+            || ext3 > max_hang
+            || (aligned as f64) < (aligned + ext5 + ext3) as f64 * int_frac
+        {
+            OverlapClass::Internal
+        } else if (left_a <= left_b && right_a <= right_b)
+            || (left_a >= left_b && right_a >= right_b)
+        {
+            OverlapClass::Containment
+        } else {
+            OverlapClass::Dovetail
+        }
+    }
+}
+
+impl std::str::FromStr for OverlapClass {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "containment" => Ok(OverlapClass::Containment),
+            "dovetail" => Ok(OverlapClass::Dovetail),
+            "internal" => Ok(OverlapClass::Internal),
+            _ => Err(format!(
+                "{} isn't an overlap class (containment|dovetail|internal)",
+                s
+            )),
+        }
+    }
+}
+
+/// Comparison operator of a [TagCondition]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TagOperator {
+    /// `==` or `=`
+    Equal,
+    /// `!=`
+    NotEqual,
+    /// `<`
+    Lower,
+    /// `<=`
+    LowerOrEqual,
+    /// `>`
+    Greater,
+    /// `>=`
+    GreaterOrEqual,
+}
+
+/// Condition on a SAM-style tag of a paf record, written `tag:type` operator value, for example `dv:f<0.2`, `NM:i<=100` or `tp:A==P`
+///
+/// Tags of type `i` and `f` are compared as numbers, others as strings (only `==` and `!=`).
+/// A record without the tag satisfies the condition.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TagCondition {
+    /// name and type of tag, for example `dv:f:`
+    pub tag: String,
+    /// comparison operator
+    pub operator: TagOperator,
+    /// value compared to tag value
+    pub value: String,
+}
+
+impl TagCondition {
+    /// Return true if tag is absent in `tags` or if tag value satisfies condition
+    pub fn check<'a, I>(&self, mut tags: I) -> bool
+    where
+        I: Iterator<Item = &'a str>,
+    {
+        let value = match tags.find_map(|x| x.strip_prefix(self.tag.as_str())) {
+            Some(value) => value,
+            None => return true,
+        };
+
+        if self.is_numeric() {
+            let (value, reference) = match (value.parse::<f64>(), self.value.parse::<f64>()) {
+                (Ok(value), Ok(reference)) => (value, reference),
+                _ => return false,
+            };
+
+            match self.operator {
+                TagOperator::Equal => value == reference,
+                TagOperator::NotEqual => value != reference,
+                TagOperator::Lower => value < reference,
+                TagOperator::LowerOrEqual => value <= reference,
+                TagOperator::Greater => value > reference,
+                TagOperator::GreaterOrEqual => value >= reference,
+            }
+        } else {
+            match self.operator {
+                TagOperator::NotEqual => value != self.value,
+                _ => value == self.value,
+            }
+        }
+    }
+
+    fn is_numeric(&self) -> bool {
+        self.tag.ends_with(":i:") || self.tag.ends_with(":f:")
+    }
+}
+
+impl std::str::FromStr for TagCondition {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let error = || {
+            format!(
+                "{} isn't a tag condition, expected format is tag:type operator value, for example dv:f<0.2",
+                s
+            )
+        };
+
+        let position = s.find(['=', '!', '<', '>']).ok_or_else(error)?;
+        let (tag, condition) = s.split_at(position);
+
+        let (operator, value) = [
+            ("==", TagOperator::Equal),
+            ("!=", TagOperator::NotEqual),
+            ("<=", TagOperator::LowerOrEqual),
+            (">=", TagOperator::GreaterOrEqual),
+            ("=", TagOperator::Equal),
+            ("<", TagOperator::Lower),
+            (">", TagOperator::Greater),
+        ]
+        .iter()
+        .find_map(|(symbol, operator)| condition.strip_prefix(symbol).map(|v| (*operator, v)))
+        .ok_or_else(error)?;
+
+        let mut fields = tag.trim().split(':');
+        let (name, kind) = match (fields.next(), fields.next(), fields.next()) {
+            (Some(name), Some(kind), None) if name.len() == 2 && kind.len() == 1 => (name, kind),
+            _ => return Err(error()),
+        };
+
+        let condition = TagCondition {
+            tag: format!("{}:{}:", name, kind),
+            operator,
+            value: value.trim().to_string(),
+        };
+
+        if condition.is_numeric() && condition.value.parse::<f64>().is_err() {
+            return Err(format!("{} value of {} isn't a number", condition.value, s));
+        }
+
+        if !condition.is_numeric()
+            && operator != TagOperator::Equal
+            && operator != TagOperator::NotEqual
+        {
+            return Err(format!(
+                "{} tag of type {} can only be compared with == or !=",
+                s, kind
+            ));
+        }
+
+        Ok(condition)
+    }
+}
+
+/// Minimal mapping quality, block length and identity of an overlap, with count of overlaps dropped by each filter
+///
+/// Filters are applied in this order and a dropped overlap is counted only by the first filter it fails.
+/// Self overlaps are dropped, except if `keep_self` is set, and duplicates (A→B and B→A, or A→B twice,
+/// with intersecting intervals) are dropped if `remove_duplicate` is set, intervals of all kept overlaps are then stored. If `classes` isn't empty,
+/// overlaps whose [OverlapClass] isn't in `classes` are dropped. Paf records are dropped if their
+/// alignment type (`tp:A` tag) isn't in `alignment_types` or if they don't satisfy all `tag_conditions`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct OverlapFilter {
+    /// minimal mapping quality, overlap without mapping quality (255) isn't filtered
+    pub min_mapq: u8,
+    /// minimal alignment block length
+    pub min_block_length: u64,
+    /// minimal identity, number of matches divided by alignment block length
+    pub min_identity: f64,
+    /// number of overlaps dropped by mapping quality filter
+    pub dropped_mapq: u64,
+    /// number of overlaps dropped by block length filter
+    pub dropped_block_length: u64,
+    /// number of overlaps dropped by identity filter
+    pub dropped_identity: u64,
+    /// keep overlaps between a read and itself
+    pub keep_self: bool,
+    /// drop reciprocal duplicates of an overlap
+    pub remove_duplicate: bool,
+    /// number of self overlaps dropped
+    pub dropped_self: u64,
+    /// number of reciprocal duplicates dropped
+    pub dropped_duplicate: u64,
+    /// overlap classes counted, empty means all classes
+    pub classes: Vec<OverlapClass>,
+    /// maximal overhang on each side of a non internal overlap
+    pub max_hang: u32,
+    /// minimal ratio between aligned length and aligned length plus overhangs of a non internal overlap
+    pub int_frac: f64,
+    /// number of overlaps dropped by class filter
+    pub dropped_class: u64,
+    /// overlaps are split on insertions or deletions larger than this value, if CIGAR is available
+    pub split_indel: Option<u32>,
+    /// alignment types (`tp:A` tag) kept, empty means all types, record without type is kept
+    pub alignment_types: Vec<char>,
+    /// number of overlaps dropped by alignment type filter
+    pub dropped_alignment_type: u64,
+    /// conditions on tags that a paf record must satisfy
+    pub tag_conditions: Vec<TagCondition>,
+    /// number of bases trimmed on each side of overlap interval not at a read end
+    pub trim: u32,
+    /// fraction of overlap interval length trimmed on each side not at a read end, the largest of `trim` and this value is used
+    pub trim_fraction: f64,
+    /// number of overlaps dropped by tag conditions
+    pub dropped_tag: u64,
+    pairs: rustc_hash::FxHashMap<String, rustc_hash::FxHashMap<String, Vec<PairIntervals>>>,
+}
+
+impl Default for OverlapFilter {
+    fn default() -> Self {
+        OverlapFilter {
+            min_mapq: 0,
+            min_block_length: 0,
+            min_identity: 0.0,
+            dropped_mapq: 0,
+            dropped_block_length: 0,
+            dropped_identity: 0,
+            keep_self: false,
+            remove_duplicate: false,
+            dropped_self: 0,
+            dropped_duplicate: 0,
+            classes: Vec::new(),
+            max_hang: 1000,
+            int_frac: 0.8,
+            dropped_class: 0,
+            split_indel: None,
+            alignment_types: Vec::new(),
+            dropped_alignment_type: 0,
+            tag_conditions: Vec::new(),
+            trim: 0,
+            trim_fraction: 0.0,
+            dropped_tag: 0,
+            pairs: rustc_hash::FxHashMap::default(),
+        }
+    }
+}
+
+impl OverlapFilter {
+    /// Create a new filter, with 0 for each value no overlap is dropped
+    pub fn new(min_mapq: u8, min_block_length: u64, min_identity: f64) -> Self {
+        OverlapFilter {
+            min_mapq,
+            min_block_length,
+            min_identity,
+            ..Default::default()
+        }
+    }
+
+    /// Return true if overlap pass all filters, otherwise drop counter of the failed filter is incremented
+    pub fn keep(&mut self, mapq: u8, block_length: u64, identity: f64) -> bool {
+        if mapq != MAPQ_UNAVAILABLE && mapq < self.min_mapq {
+            self.dropped_mapq += 1;
+            false
+        } else if block_length < self.min_block_length {
+            self.dropped_block_length += 1;
+            false
+        } else if identity < self.min_identity {
+            self.dropped_identity += 1;
+            false
+        } else {
+            true
+        }
+    }
+
+    /// Return true if alignment type of record is selected and if record satisfies all tag conditions
+    pub fn keep_tags<'a, I>(&mut self, tags: I) -> bool
+    where
+        I: Iterator<Item = &'a str> + Clone,
+    {
+        if !self.alignment_types.is_empty() {
+            if let Some(tp) = tags.clone().find_map(|x| x.strip_prefix("tp:A:")) {
+                if !tp
+                    .chars()
+                    .next()
+                    .is_some_and(|t| self.alignment_types.contains(&t))
+                {
+                    self.dropped_alignment_type += 1;
+                    return false;
+                }
+            }
+        }
+
+        if !self.tag_conditions.iter().all(|c| c.check(tags.clone())) {
+            self.dropped_tag += 1;
+            return false;
+        }
+
+        true
+    }
+
+    /// Trim overlap interval, a side closer to a read end than trimmed length isn't trimmed, return None if interval becomes empty
+    pub fn trim_interval(&self, ovl: (u32, u32), len: usize) -> Option<(u32, u32)> {
+        let margin = self
+            .trim
+            .max((ovl.1.saturating_sub(ovl.0) as f64 * self.trim_fraction) as u32);
+
+        if margin == 0 {
+            return Some(ovl);
+        }
+
+        let begin = if ovl.0 > margin {
+            ovl.0 + margin
+        } else {
+            ovl.0
+        };
+        let end = if (ovl.1 as usize) + (margin as usize) < len {
+            ovl.1 - margin
+        } else {
+            ovl.1
+        };
+
+        if begin < end {
+            Some((begin, end))
+        } else {
+            None
+        }
+    }
+
+    /// Return true if overlap isn't a self overlap, has a selected class and isn't an already seen reciprocal duplicate
+    pub fn keep_pair(&mut self, pair: &reads2ovl::OverlapPair) -> bool {
+        if pair.id_a == pair.id_b && !self.keep_self {
+            self.dropped_self += 1;
+            return false;
+        }
+
+        if !self.classes.is_empty()
+            && !self
+                .classes
+                .contains(&OverlapClass::classify(pair, self.max_hang, self.int_frac))
+        {
+            self.dropped_class += 1;
+            return false;
+        }
+
+        if !self.remove_duplicate {
+            return true;
+        }
+
+        let (first, second, ovls) = if pair.id_a <= pair.id_b {
+            (&pair.id_a, &pair.id_b, (pair.ovl_a, pair.ovl_b))
+        } else {
+            (&pair.id_b, &pair.id_a, (pair.ovl_b, pair.ovl_a))
+        };
+
+        if !self.pairs.contains_key(first) {
+            self.pairs
+                .insert(first.clone(), rustc_hash::FxHashMap::default());
+        }
+        let partners = self.pairs.get_mut(first).unwrap();
+        if !partners.contains_key(second) {
+            partners.insert(second.clone(), Vec::new());
+        }
+        let seen = partners.get_mut(second).unwrap();
+
+        if seen
+            .iter()
+            .any(|x| intersect(x.0, ovls.0) && intersect(x.1, ovls.1))
+        {
+            self.dropped_duplicate += 1;
+            false
+        } else {
+            seen.push(ovls);
+            true
+        }
+    }
+
+    /// Total number of dropped overlaps
+    pub fn dropped(&self) -> u64 {
+        self.dropped_mapq
+            + self.dropped_block_length
+            + self.dropped_identity
+            + self.dropped_self
+            + self.dropped_duplicate
+            + self.dropped_class
+            + self.dropped_alignment_type
+            + self.dropped_tag
+    }
+
+    /// Log number of overlaps dropped by each filter as warnings, nothing is logged if no overlap is dropped
+    pub fn log(&self) {
+        if self.dropped() == 0 {
+            return;
+        }
+
+        warn!(
+            "Overlaps dropped by mapping quality filter: {}",
+            self.dropped_mapq
+        );
+        warn!(
+            "Overlaps dropped by block length filter: {}",
+            self.dropped_block_length
+        );
+        warn!(
+            "Overlaps dropped by identity filter: {}",
+            self.dropped_identity
+        );
+        warn!("Self overlaps dropped: {}", self.dropped_self);
+        warn!("Overlaps dropped by class filter: {}", self.dropped_class);
+        warn!(
+            "Overlaps dropped by alignment type filter: {}",
+            self.dropped_alignment_type
+        );
+        warn!("Overlaps dropped by tag filter: {}", self.dropped_tag);
+        warn!(
+            "Reciprocal duplicate overlaps dropped: {}",
+            self.dropped_duplicate
+        );
+    }
+}
+
+fn intersect(a: (u32, u32), b: (u32, u32)) -> bool {
+    a.0 < b.1 && b.0 < a.1
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn keep() {
+        let mut filter = OverlapFilter::new(10, 500, 0.8);
+
+        assert!(filter.keep(10, 500, 0.8));
+        assert!(filter.keep(MAPQ_UNAVAILABLE, 1000, 0.9));
+
+        assert!(!filter.keep(5, 100, 0.1));
+        assert!(!filter.keep(20, 100, 0.9));
+        assert!(!filter.keep(20, 1000, 0.5));
+        assert!(!filter.keep(20, 1000, 0.79));
+
+        assert_eq!(1, filter.dropped_mapq);
+        assert_eq!(1, filter.dropped_block_length);
+        assert_eq!(2, filter.dropped_identity);
+        assert_eq!(4, filter.dropped());
+    }
+
+    fn pair(
+        id_a: &str,
+        ovl_a: (u32, u32),
+        id_b: &str,
+        ovl_b: (u32, u32),
+    ) -> reads2ovl::OverlapPair {
+        reads2ovl::OverlapPair {
+            id_a: id_a.to_string(),
+            ovl_a,
+            len_a: 10000,
+            id_b: id_b.to_string(),
+            ovl_b,
+            len_b: 10000,
+            reverse: false,
+        }
+    }
+
+    #[test]
+    fn self_overlap() {
+        let mut filter = OverlapFilter::default();
+
+        assert!(!filter.keep_pair(&pair("1", (0, 10000), "1", (0, 10000))));
+        assert!(filter.keep_pair(&pair("1", (0, 500), "2", (100, 600))));
+        assert!(filter.keep_pair(&pair("2", (100, 600), "1", (0, 500))));
+        assert_eq!(1, filter.dropped_self);
+
+        filter.keep_self = true;
+        assert!(filter.keep_pair(&pair("1", (0, 10000), "1", (0, 10000))));
+    }
+
+    #[test]
+    fn reciprocal_duplicate() {
+        let mut filter = OverlapFilter {
+            remove_duplicate: true,
+            ..Default::default()
+        };
+
+        assert!(filter.keep_pair(&pair("1", (0, 500), "2", (100, 600))));
+        assert!(!filter.keep_pair(&pair("2", (110, 600), "1", (0, 490))));
+        assert!(!filter.keep_pair(&pair("1", (0, 500), "2", (100, 600))));
+        assert!(filter.keep_pair(&pair("1", (5000, 5500), "2", (100, 600))));
+        assert!(filter.keep_pair(&pair("2", (0, 500), "3", (100, 600))));
+
+        assert_eq!(2, filter.dropped_duplicate);
+        assert_eq!(2, filter.dropped());
+    }
+
+    #[test]
+    fn classify() {
+        let dovetail = pair("1", (5000, 10000), "2", (0, 5000));
+        assert_eq!(
+            OverlapClass::Dovetail,
+            OverlapClass::classify(&dovetail, 1000, 0.8)
+        );
+
+        let containment = pair("1", (0, 10000), "2", (2000, 8000));
+        assert_eq!(
+            OverlapClass::Containment,
+            OverlapClass::classify(&containment, 1000, 0.8)
+        );
+
+        let internal = pair("1", (4000, 5000), "2", (6000, 7000));
+        assert_eq!(
+            OverlapClass::Internal,
+            OverlapClass::classify(&internal, 1000, 0.8)
+        );
+
+        let one_side = pair("1", (1500, 9000), "2", (1500, 9000));
+        assert_eq!(
+            OverlapClass::Internal,
+            OverlapClass::classify(&one_side, 1000, 0.8)
+        );
+        assert_eq!(
+            OverlapClass::Containment,
+            OverlapClass::classify(&one_side, 2000, 0.7)
+        );
+
+        let mut reverse = pair("1", (5000, 10000), "2", (5000, 10000));
+        assert_eq!(
+            OverlapClass::Internal,
+            OverlapClass::classify(&reverse, 1000, 0.8)
+        );
+        reverse.reverse = true;
+        assert_eq!(
+            OverlapClass::Dovetail,
+            OverlapClass::classify(&reverse, 1000, 0.8)
+        );
+    }
+
+    #[test]
+    fn class_filter() {
+        let mut filter = OverlapFilter {
+            classes: vec![OverlapClass::Dovetail, OverlapClass::Containment],
+            ..Default::default()
+        };
+
+        assert!(filter.keep_pair(&pair("1", (5000, 10000), "2", (0, 5000))));
+        assert!(filter.keep_pair(&pair("1", (0, 10000), "2", (2000, 8000))));
+        assert!(!filter.keep_pair(&pair("1", (4000, 5000), "2", (6000, 7000))));
+
+        assert_eq!(1, filter.dropped_class);
+    }
+
+    #[test]
+    fn tag_condition_parsing() {
+        assert_eq!(
+            TagCondition {
+                tag: "dv:f:".to_string(),
+                operator: TagOperator::Lower,
+                value: "0.2".to_string()
+            },
+            "dv:f<0.2".parse().unwrap()
+        );
+        assert_eq!(
+            TagCondition {
+                tag: "NM:i:".to_string(),
+                operator: TagOperator::LowerOrEqual,
+                value: "100".to_string()
+            },
+            "NM:i <= 100".parse().unwrap()
+        );
+        assert_eq!(
+            TagCondition {
+                tag: "tp:A:".to_string(),
+                operator: TagOperator::Equal,
+                value: "P".to_string()
+            },
+            "tp:A==P".parse().unwrap()
+        );
+
+        assert!("dv:f".parse::<TagCondition>().is_err());
+        assert!("dv<0.2".parse::<TagCondition>().is_err());
+        assert!("dv:f<high".parse::<TagCondition>().is_err());
+        assert!("tp:A<P".parse::<TagCondition>().is_err());
+    }
+
+    #[test]
+    fn tag_condition() {
+        let tags = ["tp:A:S", "cm:i:11", "dv:f:0.1755"];
+
+        let dv: TagCondition = "dv:f<0.2".parse().unwrap();
+        assert!(dv.check(tags.iter().cloned()));
+        let dv: TagCondition = "dv:f>=0.2".parse().unwrap();
+        assert!(!dv.check(tags.iter().cloned()));
+
+        let tp: TagCondition = "tp:A!=S".parse().unwrap();
+        assert!(!tp.check(tags.iter().cloned()));
+
+        let nm: TagCondition = "NM:i<100".parse().unwrap();
+        assert!(nm.check(tags.iter().cloned()));
+    }
+
+    #[test]
+    fn keep_tags() {
+        let mut filter = OverlapFilter {
+            alignment_types: vec!['P'],
+            tag_conditions: vec!["dv:f<0.2".parse().unwrap()],
+            ..Default::default()
+        };
+
+        assert!(filter.keep_tags(["tp:A:P", "dv:f:0.1"].iter().cloned()));
+        assert!(!filter.keep_tags(["tp:A:S", "dv:f:0.1"].iter().cloned()));
+        assert!(!filter.keep_tags(["tp:A:P", "dv:f:0.3"].iter().cloned()));
+        assert!(filter.keep_tags(["cm:i:11"].iter().cloned()));
+
+        assert_eq!(1, filter.dropped_alignment_type);
+        assert_eq!(1, filter.dropped_tag);
+    }
+
+    #[test]
+    fn trim_interval() {
+        let mut filter = OverlapFilter {
+            trim: 100,
+            ..Default::default()
+        };
+
+        assert_eq!(Some((600, 900)), filter.trim_interval((500, 1000), 2000));
+        assert_eq!(Some((0, 900)), filter.trim_interval((0, 1000), 2000));
+        assert_eq!(Some((50, 900)), filter.trim_interval((50, 1000), 2000));
+        assert_eq!(Some((600, 2000)), filter.trim_interval((500, 2000), 2000));
+        assert_eq!(Some((600, 1950)), filter.trim_interval((500, 1950), 2000));
+        assert_eq!(None, filter.trim_interval((500, 650), 2000));
+
+        filter.trim_fraction = 0.3;
+        assert_eq!(Some((650, 850)), filter.trim_interval((500, 1000), 2000));
+        assert_eq!(Some((0, 700)), filter.trim_interval((0, 1000), 2000));
+    }
+
+    #[test]
+    fn default_keep_all() {
+        let mut filter = OverlapFilter::default();
+
+        assert!(filter.keep(0, 0, 0.0));
+        assert_eq!(0, filter.dropped());
+    }
+}
@@ -20,24 +20,32 @@
    SOFTWARE.
 */
 
+//! Storage of overlaps in memory
+
 /* crate use */
 use anyhow::Result;
 
 /* local use */
 use crate::reads2ovl;
 
+/// Keep all overlaps in memory
 pub struct FullMemory {
     reads2ovl: reads2ovl::MapReads2Ovl,
+    details: Option<reads2ovl::MapReads2Detail>,
     no_overlap: Vec<(u32, u32)>,
     read_buffer_size: usize,
+    overlap_filter: reads2ovl::OverlapFilter,
 }
 
 impl FullMemory {
+    /// Create a new empty storage, `read_buffer_size` is size of buffer used to read overlap file
     pub fn new(read_buffer_size: usize) -> Self {
         FullMemory {
             reads2ovl: rustc_hash::FxHashMap::default(),
+            details: None,
             no_overlap: Vec::new(),
             read_buffer_size,
+            overlap_filter: reads2ovl::OverlapFilter::default(),
         }
     }
 }
@@ -89,10 +97,51 @@ impl reads2ovl::Reads2Ovl for FullMemory {
         Ok(())
     }
 
+    fn add_overlap_pair(&mut self, pair: reads2ovl::OverlapPair) -> Result<()> {
+        if let Some(details) = self.details.as_mut() {
+            details
+                .entry(pair.id_a.clone())
+                .or_default()
+                .push(reads2ovl::OverlapDetail {
+                    interval: pair.ovl_a,
+                    reverse: pair.reverse,
+                    partner: reads2ovl::name_hash(&pair.id_b),
+                });
+            details
+                .entry(pair.id_b.clone())
+                .or_default()
+                .push(reads2ovl::OverlapDetail {
+                    interval: pair.ovl_b,
+                    reverse: pair.reverse,
+                    partner: reads2ovl::name_hash(&pair.id_a),
+                });
+        }
+
+        self.add_overlap_and_length(pair.id_a, pair.ovl_a, pair.len_a)?;
+        self.add_overlap_and_length(pair.id_b, pair.ovl_b, pair.len_b)
+    }
+
     fn get_reads(&self) -> rustc_hash::FxHashSet<String> {
         self.reads2ovl.keys().map(|x| x.to_string()).collect()
     }
 
+    fn keep_details(&mut self) -> bool {
+        self.details
+            .get_or_insert_with(rustc_hash::FxHashMap::default);
+
+        true
+    }
+
+    fn get_details(&mut self, new: &mut reads2ovl::MapReads2Detail) {
+        if let Some(details) = self.details.as_mut() {
+            std::mem::swap(details, new);
+        }
+    }
+
+    fn overlap_filter(&mut self) -> Option<&mut reads2ovl::OverlapFilter> {
+        Some(&mut self.overlap_filter)
+    }
+
     fn read_buffer_size(&self) -> usize {
         self.read_buffer_size
     }
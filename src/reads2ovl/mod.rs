@@ -20,41 +20,185 @@ OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
 SOFTWARE.
  */
 
+//! Collect overlap intervals of each read from an overlap file
+
 /* crate use */
 use anyhow::{anyhow, bail, Context, Result};
 
 /* local mod */
+pub mod filter;
 pub mod fullmemory;
 pub mod ondisk;
+pub mod streaming;
 
 /* stuff declare in submod need to be accessible from mod level */
+pub use self::filter::*;
 pub use self::fullmemory::*;
 pub use self::ondisk::*;
+pub use self::streaming::*;
 
 /* std use */
+use std::hash::{Hash, Hasher};
+use std::io::Read;
 
 /* local use */
 use crate::error;
 use crate::io;
 use crate::util;
 
+/// First bytes of a yacrd overlap file, last byte is the version of format
+pub const YOVL_MAGIC: &[u8; 5] = b"YOVL\x01";
+
+/// Map a read name to its overlap intervals and its length
 pub type MapReads2Ovl = rustc_hash::FxHashMap<String, (Vec<(u32, u32)>, usize)>;
 
+/// Strand and partner of an overlap of a read
+#[derive(Debug, Clone, PartialEq)]
+pub struct OverlapDetail {
+    /// overlap interval on the read
+    pub interval: (u32, u32),
+    /// partner is aligned on reverse strand
+    pub reverse: bool,
+    /// FxHash of partner name
+    pub partner: u64,
+}
+
+/// Map a read name to strand and partner of its overlaps
+pub type MapReads2Detail = rustc_hash::FxHashMap<String, Vec<OverlapDetail>>;
+
+/// Hash of a read name, used to store a read name in a compact way
+pub(crate) fn name_hash(id: &str) -> u64 {
+    let mut hasher = rustc_hash::FxHasher::default();
+    id.hash(&mut hasher);
+    hasher.finish()
+}
+
+/// An overlap between read a (query) and read b (target)
+#[derive(Debug, Clone, PartialEq)]
+pub struct OverlapPair {
+    /// name of read a
+    pub id_a: String,
+    /// overlap interval on read a
+    pub ovl_a: (u32, u32),
+    /// length of read a
+    pub len_a: usize,
+    /// name of read b
+    pub id_b: String,
+    /// overlap interval on read b
+    pub ovl_b: (u32, u32),
+    /// length of read b
+    pub len_b: usize,
+    /// read b is aligned on reverse strand of read a
+    pub reverse: bool,
+}
+
+/// Add overlap of a SAM or BAM record, unmapped records are ignored
+fn add_alignment<R>(
+    ovl: &mut R,
+    header: &noodles::sam::Header,
+    record: &noodles::sam::alignment::RecordBuf,
+) -> Result<()>
+where
+    R: Reads2Ovl + ?Sized,
+{
+    let flags = record.flags();
+    let (ref_id, pos) = match (record.reference_sequence_id(), record.alignment_start()) {
+        (Some(ref_id), Some(pos))
+            if !flags.is_unmapped() && !record.cigar().as_ref().is_empty() =>
+        {
+            (ref_id, usize::from(pos) - 1)
+        }
+        _ => return Ok(()),
+    };
+
+    let (id_b, reference) = header
+        .reference_sequences()
+        .get_index(ref_id)
+        .with_context(|| error::Error::MissingReferenceLength {
+            name: ref_id.to_string(),
+        })?;
+
+    let cigar = io::record_cigar(record.cigar().as_ref());
+    let mapq = record
+        .mapping_quality()
+        .map_or(MAPQ_UNAVAILABLE, |mapq| mapq.get());
+    let edit_distance = record
+        .data()
+        .get(&noodles::sam::alignment::record::data::field::Tag::EDIT_DISTANCE)
+        .and_then(|value| value.as_int())
+        .map(|value| value as u32);
+    let (block_length, identity) = io::cigar_identity(&cigar, edit_distance);
+    if !ovl
+        .overlap_filter()
+        .map_or(true, |f| f.keep(mapq, block_length, identity))
+    {
+        return Ok(());
+    }
+
+    let (len_a, ovl_a, ovl_b) = io::cigar2intervals(
+        cigar.iter().cloned(),
+        flags.is_reverse_complemented(),
+        pos as u32,
+    );
+
+    let pair = OverlapPair {
+        id_a: record.name().map(|x| x.to_string()).unwrap_or_default(),
+        ovl_a,
+        len_a,
+        id_b: id_b.to_string(),
+        ovl_b,
+        len_b: reference.length().get(),
+        reverse: flags.is_reverse_complemented(),
+    };
+
+    if ovl.overlap_filter().map_or(true, |f| f.keep_pair(&pair)) {
+        ovl.add_overlap_pair_cigar(pair, Some(&cigar))?;
+    }
+
+    Ok(())
+}
+
+/// Store overlap intervals and length of each read
 pub trait Reads2Ovl {
+    /// Read overlaps present in file, format is detected with content or name of file
     fn init(&mut self, filename: &str) -> Result<()> {
-        self.sub_init(filename)
+        let (input, _) = util::read_file(filename, self.read_buffer_size())?;
+
+        self.init_from_reader(input, filename, None)
     }
 
-    fn sub_init(&mut self, filename: &str) -> Result<()> {
-        let (input, _) = util::read_file(filename, self.read_buffer_size())?;
+    /// Read overlaps present in an opened file, if `format` is None format is detected with content or name of file
+    fn init_from_reader(
+        &mut self,
+        input: Box<dyn std::io::BufRead>,
+        filename: &str,
+        format: Option<util::FileType>,
+    ) -> Result<()> {
+        self.sub_init(input, filename, format)
+    }
 
-        match util::get_file_type(filename) {
+    /// Read overlaps present in an opened file, called by [Reads2Ovl::init_from_reader]
+    fn sub_init(
+        &mut self,
+        mut input: Box<dyn std::io::BufRead>,
+        filename: &str,
+        format: Option<util::FileType>,
+    ) -> Result<()> {
+        let format = format.or_else(|| util::detect_file_type(&mut input, filename));
+
+        match format {
             Some(util::FileType::Paf) => self
                 .init_paf(input)
                 .with_context(|| anyhow!("Filename: {}", filename.to_string()))?,
             Some(util::FileType::M4) => self
                 .init_m4(input)
                 .with_context(|| anyhow!("Filename: {}", filename.to_string()))?,
+            Some(util::FileType::Sam) => self
+                .init_sam(input)
+                .with_context(|| anyhow!("Filename: {}", filename.to_string()))?,
+            Some(util::FileType::Bam) => self
+                .init_bam(input)
+                .with_context(|| anyhow!("Filename: {}", filename.to_string()))?,
             Some(util::FileType::Fasta) => bail!(error::Error::CantRunOperationOnFile {
                 operation: "overlap parsing".to_string(),
                 filetype: util::FileType::Fasta,
@@ -70,17 +214,25 @@ pub trait Reads2Ovl {
                 filetype: util::FileType::Yacrd,
                 filename: filename.to_string()
             }),
-            None | Some(util::FileType::YacrdOverlap) => {
+            Some(util::FileType::YacrdOverlap) => self
+                .init_yovl(input)
+                .with_context(|| anyhow!("Filename: {}", filename.to_string()))?,
+            None => {
                 bail!(error::Error::UnableToDetectFileFormat {
                     filename: filename.to_string()
                 })
             }
         }
 
+        if let Some(filter) = self.overlap_filter() {
+            filter.log();
+        }
+
         Ok(())
     }
 
-    fn init_paf(&mut self, input: Box<dyn std::io::Read>) -> Result<()> {
+    /// Read overlaps present in a paf stream
+    fn init_paf(&mut self, input: Box<dyn std::io::BufRead>) -> Result<()> {
         let mut reader = csv::ReaderBuilder::new()
             .delimiter(b'\t')
             .has_headers(false)
@@ -96,23 +248,42 @@ pub trait Reads2Ovl {
                         format: util::FileType::Paf,
                     })?;
 
-            let id_a = record.read_a.to_string();
-            let id_b = record.read_b.to_string();
+            if let Some(filter) = self.overlap_filter() {
+                if !filter.keep(record.mapq, record.block_length, record.identity())
+                    || !filter.keep_tags(rec.iter().skip(12))
+                {
+                    continue;
+                }
+            }
 
-            let len_a = record.length_a;
-            let len_b = record.length_b;
+            let pair = OverlapPair {
+                id_a: record.read_a.to_string(),
+                ovl_a: (record.begin_a, record.end_a),
+                len_a: record.length_a,
+                id_b: record.read_b.to_string(),
+                ovl_b: (record.begin_b, record.end_b),
+                len_b: record.length_b,
+                reverse: record.strand == '-',
+            };
 
-            let ovl_a = (record.begin_a, record.end_a);
-            let ovl_b = (record.begin_b, record.end_b);
+            let cigar = match (
+                self.overlap_filter().and_then(|f| f.split_indel),
+                io::paf_tag(&rec, "cg:Z:"),
+            ) {
+                (Some(_), Some(cigar)) => Some(io::parse_cigar(cigar)?),
+                _ => None,
+            };
 
-            self.add_overlap_and_length(id_a, ovl_a, len_a)?;
-            self.add_overlap_and_length(id_b, ovl_b, len_b)?;
+            if self.overlap_filter().map_or(true, |f| f.keep_pair(&pair)) {
+                self.add_overlap_pair_cigar(pair, cigar.as_deref())?;
+            }
         }
 
         Ok(())
     }
 
-    fn init_m4(&mut self, input: Box<dyn std::io::Read>) -> Result<()> {
+    /// Read overlaps present in a m4 stream
+    fn init_m4(&mut self, input: Box<dyn std::io::BufRead>) -> Result<()> {
         let mut reader = csv::ReaderBuilder::new()
             .delimiter(b' ')
             .has_headers(false)
@@ -128,37 +299,241 @@ pub trait Reads2Ovl {
                         format: util::FileType::M4,
                     })?;
 
-            let id_a = record.read_a.to_string();
-            let id_b = record.read_b.to_string();
+            if !self.overlap_filter().map_or(true, |f| {
+                f.keep(MAPQ_UNAVAILABLE, record.block_length(), record.identity())
+            }) {
+                continue;
+            }
+
+            let pair = OverlapPair {
+                id_a: record.read_a.to_string(),
+                ovl_a: (record.begin_a, record.end_a),
+                len_a: record.length_a,
+                id_b: record.read_b.to_string(),
+                ovl_b: (record.begin_b, record.end_b),
+                len_b: record.length_b,
+                reverse: record.strand_a != record.strand_b,
+            };
+
+            if self.overlap_filter().map_or(true, |f| f.keep_pair(&pair)) {
+                self.add_overlap_pair_cigar(pair, None)?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Read overlaps present in a sam stream, intervals are computed from CIGAR
+    fn init_sam(&mut self, input: Box<dyn std::io::BufRead>) -> Result<()> {
+        let mut reader = noodles::sam::io::Reader::new(input);
+
+        let header =
+            reader
+                .read_header()
+                .with_context(|| error::Error::ReadingErrorNoFilename {
+                    format: util::FileType::Sam,
+                })?;
+        let mut record = noodles::sam::alignment::RecordBuf::default();
+
+        while reader
+            .read_record_buf(&header, &mut record)
+            .with_context(|| error::Error::ReadingErrorNoFilename {
+                format: util::FileType::Sam,
+            })?
+            != 0
+        {
+            add_alignment(self, &header, &record)?;
+        }
+
+        Ok(())
+    }
 
-            let len_a = record.length_a;
-            let len_b = record.length_b;
+    /// Read overlaps present in a bam stream, intervals are computed from CIGAR
+    fn init_bam(&mut self, input: Box<dyn std::io::BufRead>) -> Result<()> {
+        let mut reader = noodles::bam::io::Reader::from(input);
 
-            let ovl_a = (record.begin_a, record.end_a);
-            let ovl_b = (record.begin_b, record.end_b);
+        let header =
+            reader
+                .read_header()
+                .with_context(|| error::Error::ReadingErrorNoFilename {
+                    format: util::FileType::Bam,
+                })?;
+        let mut record = noodles::sam::alignment::RecordBuf::default();
 
-            self.add_overlap_and_length(id_a, ovl_a, len_a)?;
-            self.add_overlap_and_length(id_b, ovl_b, len_b)?;
+        while reader
+            .read_record_buf(&header, &mut record)
+            .with_context(|| error::Error::ReadingErrorNoFilename {
+                format: util::FileType::Bam,
+            })?
+            != 0
+        {
+            add_alignment(self, &header, &record)?;
         }
 
         Ok(())
     }
 
+    /// Read overlaps present in a yacrd overlap stream
+    fn init_yovl(&mut self, input: Box<dyn std::io::BufRead>) -> Result<()> {
+        let mut reader = input;
+
+        let mut magic = [0; 5];
+        reader
+            .read_exact(&mut magic)
+            .with_context(|| error::Error::ReadingErrorNoFilename {
+                format: util::FileType::YacrdOverlap,
+            })?;
+        if &magic != YOVL_MAGIC {
+            bail!(error::Error::ReadingErrorNoFilename {
+                format: util::FileType::YacrdOverlap,
+            });
+        }
+
+        loop {
+            let (id, len, ovls): (String, usize, Vec<(u32, u32)>) =
+                match bincode::deserialize_from(&mut reader) {
+                    Ok(record) => record,
+                    Err(e) => match *e {
+                        bincode::ErrorKind::Io(ref io_e)
+                            if io_e.kind() == std::io::ErrorKind::UnexpectedEof =>
+                        {
+                            break
+                        }
+                        _ => {
+                            return Err(e).with_context(|| error::Error::ReadingErrorNoFilename {
+                                format: util::FileType::YacrdOverlap,
+                            })
+                        }
+                    },
+                };
+
+            self.add_length(id.clone(), len);
+            for ovl in ovls {
+                let trimmed = match self.overlap_filter() {
+                    Some(filter) => filter.trim_interval(ovl, len),
+                    None => Some(ovl),
+                };
+                if let Some(ovl) = trimmed {
+                    self.add_overlap(id.clone(), ovl)?;
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Write overlaps of all reads in yacrd overlap format
+    fn write_yovl(&self, output: &mut dyn std::io::Write) -> Result<()> {
+        output
+            .write_all(YOVL_MAGIC)
+            .with_context(|| error::Error::WritingErrorNoFilename {
+                format: util::FileType::YacrdOverlap,
+            })?;
+
+        for id in self.get_reads() {
+            let len = self.length(&id);
+            let ovls = self.overlap(&id)?;
+
+            bincode::serialize_into(&mut *output, &(id, len, ovls)).with_context(|| {
+                error::Error::WritingErrorNoFilename {
+                    format: util::FileType::YacrdOverlap,
+                }
+            })?;
+        }
+
+        Ok(())
+    }
+
+    /// Move a batch of reads with their overlaps and length in `new`, return true if it's the last batch
     fn get_overlaps(&mut self, new: &mut MapReads2Ovl) -> bool;
 
-    #[allow(dead_code)]
+    /// Get overlap intervals of a read
     fn overlap(&self, id: &str) -> Result<Vec<(u32, u32)>>;
-    #[allow(dead_code)]
+    /// Get length of a read, 0 if read is unknown
     fn length(&self, id: &str) -> usize;
 
+    /// Add an overlap interval to a read
     fn add_overlap(&mut self, id: String, ovl: (u32, u32)) -> Result<()>;
+    /// Set length of a read
     fn add_length(&mut self, id: String, ovl: usize);
 
+    /// Add an overlap interval to a read and set its length
     fn add_overlap_and_length(&mut self, id: String, ovl: (u32, u32), length: usize) -> Result<()>;
 
-    #[allow(dead_code)]
+    /// Add an overlap read in overlap file, by default overlap interval is added to both reads
+    fn add_overlap_pair(&mut self, pair: OverlapPair) -> Result<()> {
+        self.add_overlap_and_length(pair.id_a, pair.ovl_a, pair.len_a)?;
+        self.add_overlap_and_length(pair.id_b, pair.ovl_b, pair.len_b)
+    }
+
+    /// Add an overlap interval to the target read of a pair only, used when query interval is trimmed to nothing
+    fn add_target_overlap(&mut self, id: String, ovl: (u32, u32), length: usize) -> Result<()> {
+        self.add_overlap_and_length(id, ovl, length)
+    }
+
+    /// Add an overlap, if `cigar` is available overlap is split on insertions or deletions larger than [OverlapFilter::split_indel],
+    /// intervals are trimmed with [OverlapFilter::trim_interval], a side trimmed to nothing is dropped but the other side is kept
+    fn add_overlap_pair_cigar(
+        &mut self,
+        pair: OverlapPair,
+        cigar: Option<&[(u8, u32)]>,
+    ) -> Result<()> {
+        let intervals = match (self.overlap_filter().and_then(|f| f.split_indel), cigar) {
+            (Some(max_indel), Some(ops)) => io::split_on_indel(
+                ops.iter().cloned(),
+                pair.ovl_a,
+                pair.ovl_b,
+                pair.reverse,
+                max_indel,
+            ),
+            _ => vec![(pair.ovl_a, pair.ovl_b)],
+        };
+
+        for (ovl_a, ovl_b) in intervals {
+            let trimmed = match self.overlap_filter() {
+                Some(filter) => (
+                    filter.trim_interval(ovl_a, pair.len_a),
+                    filter.trim_interval(ovl_b, pair.len_b),
+                ),
+                None => (Some(ovl_a), Some(ovl_b)),
+            };
+
+            match trimmed {
+                (Some(ovl_a), Some(ovl_b)) => self.add_overlap_pair(OverlapPair {
+                    ovl_a,
+                    ovl_b,
+                    ..pair.clone()
+                })?,
+                (Some(ovl_a), None) => {
+                    self.add_overlap_and_length(pair.id_a.clone(), ovl_a, pair.len_a)?
+                }
+                (None, Some(ovl_b)) => {
+                    self.add_target_overlap(pair.id_b.clone(), ovl_b, pair.len_b)?
+                }
+                (None, None) => (),
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Get name of all reads
     fn get_reads(&self) -> rustc_hash::FxHashSet<String>;
 
+    /// Keep strand and partner of each overlap, must be called before reading overlaps, return false if backend can't keep them
+    fn keep_details(&mut self) -> bool {
+        false
+    }
+
+    /// Move strand and partner of overlaps in `new`, `new` stay empty if details aren't kept
+    fn get_details(&mut self, _new: &mut MapReads2Detail) {}
+
+    /// Filter applied on overlaps, None if backend doesn't filter overlaps
+    fn overlap_filter(&mut self) -> Option<&mut OverlapFilter> {
+        None
+    }
+
+    /// Size of buffer used to read overlap file
     fn read_buffer_size(&self) -> usize;
 }
 
@@ -170,14 +545,71 @@ mod tests {
 
     extern crate tempfile;
 
-    const PAF_FILE: &'static [u8] = b"1\t12000\t20\t4500\t-\t2\t10000\t5500\t10000\t4500\t4500\t255
+    const PAF_FILE: &[u8] = b"1\t12000\t20\t4500\t-\t2\t10000\t5500\t10000\t4500\t4500\t255
 1\t12000\t5500\t10000\t-\t3\t10000\t0\t4500\t4500\t4500\t255
 ";
 
-    const M4_FILE: &'static [u8] = b"1 2 0.1 2 0 20 4500 12000 0 5500 10000 10000
+    const M4_FILE: &[u8] = b"1 2 0.1 2 0 20 4500 12000 0 5500 10000 10000
 1 3 0.1 2 0 5500 10000 12000 0 0 4500 10000
 ";
 
+    const SAM_FILE: &[u8] = b"@HD\tVN:1.6
+@SQ\tSN:2\tLN:10000
+@SQ\tSN:3\tLN:10000
+1\t16\t2\t5501\t60\t7500S4000M20D480M20S\t*\t0\t0\t*\t*
+1\t16\t3\t1\t60\t2000H4500M5500H\t*\t0\t0\t*\t*
+4\t4\t*\t0\t0\t*\t*\t0\t0\tACTG\t\"\"\"\"
+";
+
+    fn bam_file() -> Vec<u8> {
+        fn record(name: &[u8], flag: u16, ref_id: i32, pos: i32, cigar: &[(u32, u32)]) -> Vec<u8> {
+            let mut block = Vec::new();
+            block.extend(ref_id.to_le_bytes());
+            block.extend(pos.to_le_bytes());
+            block.push(name.len() as u8 + 1);
+            block.push(60);
+            block.extend(0u16.to_le_bytes());
+            block.extend((cigar.len() as u16).to_le_bytes());
+            block.extend(flag.to_le_bytes());
+            block.extend(0i32.to_le_bytes());
+            block.extend((-1i32).to_le_bytes());
+            block.extend((-1i32).to_le_bytes());
+            block.extend(0i32.to_le_bytes());
+            block.extend(name);
+            block.push(0);
+            for (len, op) in cigar {
+                block.extend((len << 4 | op).to_le_bytes());
+            }
+
+            let mut rec = (block.len() as i32).to_le_bytes().to_vec();
+            rec.extend(block);
+            rec
+        }
+
+        let mut bam = b"BAM\x01".to_vec();
+        bam.extend(0i32.to_le_bytes());
+        bam.extend(2i32.to_le_bytes());
+        for name in [b"2", b"3"] {
+            bam.extend(2i32.to_le_bytes());
+            bam.extend(name);
+            bam.push(0);
+            bam.extend(10000i32.to_le_bytes());
+        }
+
+        /* 0: M, 2: D, 4: S, 5: H */
+        bam.extend(record(
+            b"1",
+            16,
+            0,
+            5500,
+            &[(7500, 4), (4000, 0), (20, 2), (480, 0), (20, 4)],
+        ));
+        bam.extend(record(b"1", 16, 1, 0, &[(2000, 5), (4500, 0), (5500, 5)]));
+        bam.extend(record(b"4", 4, -1, -1, &[]));
+
+        bam
+    }
+
     #[test]
     fn paf() {
         let mut paf = tempfile::Builder::new()
@@ -235,4 +667,428 @@ mod tests {
         assert_eq!(vec![(5500, 10000)], ovl.overlap("2").unwrap());
         assert_eq!(vec![(0, 4500)], ovl.overlap("3").unwrap());
     }
+
+    #[test]
+    fn sam() {
+        let mut sam = tempfile::Builder::new()
+            .suffix(".sam")
+            .tempfile()
+            .expect("Can't create tmpfile");
+
+        sam.as_file_mut()
+            .write_all(SAM_FILE)
+            .expect("Error durring write of sam in temp file");
+
+        let mut ovl = FullMemory::new(8192);
+
+        ovl.init(sam.into_temp_path().to_str().unwrap())
+            .expect("Error in overlap init");
+
+        assert_eq!(
+            ["1".to_string(), "2".to_string(), "3".to_string(),]
+                .iter()
+                .cloned()
+                .collect::<rustc_hash::FxHashSet<String>>(),
+            ovl.get_reads()
+        );
+
+        assert_eq!(vec![(20, 4500), (5500, 10000)], ovl.overlap("1").unwrap());
+        assert_eq!(12000, ovl.length("1"));
+        assert_eq!(vec![(5500, 10000)], ovl.overlap("2").unwrap());
+        assert_eq!(vec![(0, 4500)], ovl.overlap("3").unwrap());
+    }
+
+    #[test]
+    fn bam() {
+        let bam = tempfile::Builder::new()
+            .suffix(".bam")
+            .tempfile()
+            .expect("Can't create tmpfile");
+
+        {
+            let mut writer = niffler::get_writer(
+                Box::new(bam.as_file()),
+                niffler::compression::Format::Gzip,
+                niffler::compression::Level::One,
+            )
+            .expect("Can't create compressed writer");
+            writer
+                .write_all(&bam_file())
+                .expect("Error durring write of bam in temp file");
+        }
+
+        let mut ovl = FullMemory::new(8192);
+
+        ovl.init(bam.into_temp_path().to_str().unwrap())
+            .expect("Error in overlap init");
+
+        assert_eq!(
+            ["1".to_string(), "2".to_string(), "3".to_string(),]
+                .iter()
+                .cloned()
+                .collect::<rustc_hash::FxHashSet<String>>(),
+            ovl.get_reads()
+        );
+
+        assert_eq!(vec![(20, 4500), (5500, 10000)], ovl.overlap("1").unwrap());
+        assert_eq!(12000, ovl.length("1"));
+        assert_eq!(vec![(5500, 10000)], ovl.overlap("2").unwrap());
+        assert_eq!(vec![(0, 4500)], ovl.overlap("3").unwrap());
+    }
+
+    #[test]
+    fn yovl() {
+        let mut paf = tempfile::Builder::new()
+            .suffix(".paf")
+            .tempfile()
+            .expect("Can't create tmpfile");
+
+        paf.as_file_mut()
+            .write_all(PAF_FILE)
+            .expect("Error durring write of paf in temp file");
+
+        let mut ovl = FullMemory::new(8192);
+
+        ovl.init(paf.into_temp_path().to_str().unwrap())
+            .expect("Error in overlap init");
+
+        let mut yovl = tempfile::Builder::new()
+            .suffix(".yovl")
+            .tempfile()
+            .expect("Can't create tmpfile");
+
+        ovl.write_yovl(yovl.as_file_mut())
+            .expect("Error durring write of yovl in temp file");
+
+        let mut ovl = FullMemory::new(8192);
+
+        ovl.init(yovl.into_temp_path().to_str().unwrap())
+            .expect("Error in overlap init");
+
+        assert_eq!(
+            ["1".to_string(), "2".to_string(), "3".to_string(),]
+                .iter()
+                .cloned()
+                .collect::<rustc_hash::FxHashSet<String>>(),
+            ovl.get_reads()
+        );
+
+        assert_eq!(vec![(20, 4500), (5500, 10000)], ovl.overlap("1").unwrap());
+        assert_eq!(12000, ovl.length("1"));
+        assert_eq!(vec![(5500, 10000)], ovl.overlap("2").unwrap());
+        assert_eq!(10000, ovl.length("2"));
+        assert_eq!(vec![(0, 4500)], ovl.overlap("3").unwrap());
+    }
+
+    const GROUPED_PAF_FILE: &[u8] = b"1\t12000\t20\t4500\t-\t2\t10000\t5500\t10000\t4500\t4500\t255
+1\t12000\t5500\t10000\t-\t3\t10000\t0\t4500\t4500\t4500\t255
+2\t10000\t5500\t10000\t-\t1\t12000\t20\t4500\t4500\t4500\t255
+3\t10000\t0\t4500\t-\t1\t12000\t5500\t10000\t4500\t4500\t255
+";
+
+    type Blocks = std::rc::Rc<std::cell::RefCell<Vec<(String, Vec<(u32, u32)>, usize)>>>;
+
+    fn streaming_collect(blocks: &Blocks) -> Streaming {
+        let collect = blocks.clone();
+
+        Streaming::new(
+            8192,
+            Box::new(move |id, ovls, length| {
+                collect.borrow_mut().push((id.to_string(), ovls, length));
+                Ok(())
+            }),
+        )
+    }
+
+    #[test]
+    fn streaming() {
+        let blocks = Blocks::default();
+        let mut ovl = streaming_collect(&blocks);
+
+        ovl.init_from_reader(
+            Box::new(GROUPED_PAF_FILE),
+            "grouped",
+            Some(util::FileType::Paf),
+        )
+        .expect("Error in overlap init");
+
+        assert_eq!(
+            vec![
+                ("1".to_string(), vec![(20, 4500), (5500, 10000)], 12000),
+                ("2".to_string(), vec![(5500, 10000)], 10000),
+                ("3".to_string(), vec![(0, 4500)], 10000),
+            ],
+            *blocks.borrow()
+        );
+        assert!(!ovl.one_direction());
+
+        let mut ovl = streaming_collect(&Blocks::default());
+        ovl.init_from_reader(Box::new(PAF_FILE), "paf", Some(util::FileType::Paf))
+            .expect("Error in overlap init");
+        assert!(ovl.one_direction());
+    }
+
+    #[test]
+    fn streaming_not_contiguous() {
+        let blocks = Blocks::default();
+        let mut ovl = streaming_collect(&blocks);
+
+        let result = ovl.init_from_reader(Box::new(PAF_FILE), "paf", Some(util::FileType::Paf));
+        assert!(result.is_ok());
+
+        let mut not_contiguous = GROUPED_PAF_FILE.to_vec();
+        not_contiguous.extend(b"1\t12000\t0\t20\t+\t4\t10000\t0\t20\t20\t20\t255\n");
+
+        let blocks = Blocks::default();
+        let mut ovl = streaming_collect(&blocks);
+
+        let result = ovl.init_from_reader(
+            Box::new(std::io::Cursor::new(not_contiguous)),
+            "not_contiguous",
+            Some(util::FileType::Paf),
+        );
+
+        assert!(result.is_err());
+        assert_eq!(3, blocks.borrow().len());
+    }
+
+    const FILTER_PAF_FILE: &[u8] = b"1\t12000\t20\t4500\t-\t2\t10000\t5500\t10000\t4000\t4500\t60
+1\t12000\t5500\t10000\t-\t3\t10000\t0\t4500\t4500\t4500\t5
+1\t12000\t100\t400\t+\t4\t10000\t0\t300\t300\t300\t255
+1\t12000\t5000\t10000\t+\t5\t10000\t0\t5000\t2000\t5000\t255
+";
+
+    #[test]
+    fn paf_filter() {
+        let mut ovl = FullMemory::new(8192);
+        *ovl.overlap_filter().unwrap() = OverlapFilter::new(10, 1000, 0.8);
+
+        ovl.init_from_reader(
+            Box::new(FILTER_PAF_FILE),
+            "filter.paf",
+            Some(util::FileType::Paf),
+        )
+        .expect("Error in overlap init");
+
+        assert_eq!(vec![(20, 4500)], ovl.overlap("1").unwrap());
+        assert_eq!(vec![(5500, 10000)], ovl.overlap("2").unwrap());
+        assert_eq!(
+            ["1".to_string(), "2".to_string()]
+                .iter()
+                .cloned()
+                .collect::<rustc_hash::FxHashSet<String>>(),
+            ovl.get_reads()
+        );
+
+        assert_eq!(1, ovl.overlap_filter().unwrap().dropped_mapq);
+        assert_eq!(1, ovl.overlap_filter().unwrap().dropped_block_length);
+        assert_eq!(1, ovl.overlap_filter().unwrap().dropped_identity);
+    }
+
+    const FILTER_SAM_FILE: &[u8] = b"@HD\tVN:1.6
+@SQ\tSN:2\tLN:10000
+@SQ\tSN:3\tLN:10000
+@SQ\tSN:4\tLN:10000
+@SQ\tSN:5\tLN:10000
+1\t0\t2\t5501\t60\t20S4480M7500S\t*\t0\t0\t*\t*\tNM:i:100
+1\t0\t3\t1\t5\t5500S4500M2000S\t*\t0\t0\t*\t*
+1\t0\t4\t1\t60\t100S300M11600S\t*\t0\t0\t*\t*
+1\t0\t5\t1\t60\t5000S5000M2000S\t*\t0\t0\t*\t*\tNM:i:3000
+";
+
+    #[test]
+    fn sam_filter() {
+        let mut ovl = FullMemory::new(8192);
+        *ovl.overlap_filter().unwrap() = OverlapFilter::new(10, 1000, 0.8);
+
+        ovl.init_from_reader(
+            Box::new(FILTER_SAM_FILE),
+            "filter.sam",
+            Some(util::FileType::Sam),
+        )
+        .expect("Error in overlap init");
+
+        assert_eq!(vec![(20, 4500)], ovl.overlap("1").unwrap());
+        assert_eq!(vec![(5500, 9980)], ovl.overlap("2").unwrap());
+
+        assert_eq!(1, ovl.overlap_filter().unwrap().dropped_mapq);
+        assert_eq!(1, ovl.overlap_filter().unwrap().dropped_block_length);
+        assert_eq!(1, ovl.overlap_filter().unwrap().dropped_identity);
+    }
+
+    #[test]
+    fn cigar_identity() {
+        assert_eq!(
+            (1000, 0.9),
+            io::cigar_identity(&[(b'S', 10), (b'M', 990), (b'D', 10)], Some(100))
+        );
+        assert_eq!(
+            (100, 0.8),
+            io::cigar_identity(&[(b'=', 80), (b'X', 10), (b'I', 10)], None)
+        );
+        assert_eq!((100, 1.0), io::cigar_identity(&[(b'M', 100)], None));
+    }
+
+    const FILTER_M4_FILE: &[u8] = b"1 2 0.1 2 0 20 4500 12000 0 5500 10000 10000
+1 3 30.0 2 0 5500 10000 12000 0 0 4500 10000
+1 4 0.05 2 0 100 400 12000 0 0 300 10000
+";
+
+    #[test]
+    fn m4_filter() {
+        let mut ovl = FullMemory::new(8192);
+        *ovl.overlap_filter().unwrap() = OverlapFilter::new(10, 1000, 0.8);
+
+        ovl.init_from_reader(
+            Box::new(FILTER_M4_FILE),
+            "filter.m4",
+            Some(util::FileType::M4),
+        )
+        .expect("Error in overlap init");
+
+        assert_eq!(vec![(20, 4500)], ovl.overlap("1").unwrap());
+        assert_eq!(vec![(5500, 10000)], ovl.overlap("2").unwrap());
+
+        assert_eq!(0, ovl.overlap_filter().unwrap().dropped_mapq);
+        assert_eq!(1, ovl.overlap_filter().unwrap().dropped_block_length);
+        assert_eq!(1, ovl.overlap_filter().unwrap().dropped_identity);
+    }
+
+    const CIGAR_PAF_FILE: &[u8] =
+        b"1\t12000\t20\t4500\t+\t2\t10000\t5500\t7980\t2480\t4480\t60\ttp:A:P\tcg:Z:1000M2000I1480M
+1\t12000\t20\t4500\t-\t3\t10000\t5500\t7980\t2480\t4480\t60\tcg:Z:1000M2000I1480M
+4\t10000\t0\t2000\t+\t5\t10000\t0\t5000\t2000\t5000\t60\tcg:Z:1000M3000D10I990M
+";
+
+    #[test]
+    fn paf_split_indel() {
+        let mut ovl = FullMemory::new(8192);
+        ovl.overlap_filter().unwrap().split_indel = Some(500);
+
+        ovl.init_from_reader(
+            Box::new(CIGAR_PAF_FILE),
+            "cigar.paf",
+            Some(util::FileType::Paf),
+        )
+        .expect("Error in overlap init");
+
+        assert_eq!(
+            vec![(20, 1020), (3020, 4500), (3500, 4500), (20, 1500)],
+            ovl.overlap("1").unwrap()
+        );
+        assert_eq!(vec![(5500, 6500), (6500, 7980)], ovl.overlap("2").unwrap());
+        assert_eq!(vec![(5500, 6500), (6500, 7980)], ovl.overlap("3").unwrap());
+        assert_eq!(vec![(0, 1000), (1000, 2000)], ovl.overlap("4").unwrap());
+        assert_eq!(vec![(0, 1000), (4000, 4990)], ovl.overlap("5").unwrap());
+    }
+
+    #[test]
+    fn paf_cigar_ignored() {
+        let mut ovl = FullMemory::new(8192);
+
+        ovl.init_from_reader(
+            Box::new(CIGAR_PAF_FILE),
+            "cigar.paf",
+            Some(util::FileType::Paf),
+        )
+        .expect("Error in overlap init");
+
+        assert_eq!(vec![(20, 4500), (20, 4500)], ovl.overlap("1").unwrap());
+        assert_eq!(vec![(0, 5000)], ovl.overlap("5").unwrap());
+    }
+
+    #[test]
+    fn ondisk_split_indel() {
+        let directory = tempfile::tempdir().expect("Can't create tmpdir");
+
+        let mut ovl = OnDisk::new(
+            directory.path().join("db").to_str().unwrap().to_string(),
+            64,
+            8192,
+        );
+        ovl.overlap_filter().unwrap().split_indel = Some(500);
+
+        ovl.init_from_reader(
+            Box::new(CIGAR_PAF_FILE),
+            "cigar.paf",
+            Some(util::FileType::Paf),
+        )
+        .expect("Error in overlap init");
+
+        assert_eq!(vec![(5500, 6500), (6500, 7980)], ovl.overlap("2").unwrap());
+        assert_eq!(vec![(0, 1000), (4000, 4990)], ovl.overlap("5").unwrap());
+    }
+
+    #[test]
+    fn paf_tag_filter() {
+        let mut ovl = FullMemory::new(8192);
+        ovl.overlap_filter().unwrap().alignment_types = vec!['S'];
+        ovl.overlap_filter().unwrap().tag_conditions =
+            vec!["cg:Z!=1000M3000D10I990M".parse().unwrap()];
+
+        ovl.init_from_reader(
+            Box::new(CIGAR_PAF_FILE),
+            "cigar.paf",
+            Some(util::FileType::Paf),
+        )
+        .expect("Error in overlap init");
+
+        assert_eq!(vec![(20, 4500)], ovl.overlap("1").unwrap());
+        assert_eq!(
+            ["1".to_string(), "3".to_string()]
+                .iter()
+                .cloned()
+                .collect::<rustc_hash::FxHashSet<String>>(),
+            ovl.get_reads()
+        );
+    }
+
+    #[test]
+    fn paf_trim() {
+        let mut ovl = FullMemory::new(8192);
+        ovl.overlap_filter().unwrap().trim = 200;
+
+        ovl.init_from_reader(Box::new(PAF_FILE), "paf", Some(util::FileType::Paf))
+            .expect("Error in overlap init");
+
+        assert_eq!(vec![(20, 4300), (5700, 9800)], ovl.overlap("1").unwrap());
+        assert_eq!(vec![(5700, 10000)], ovl.overlap("2").unwrap());
+        assert_eq!(vec![(0, 4300)], ovl.overlap("3").unwrap());
+    }
+
+    #[test]
+    fn m4_trim() {
+        let mut ovl = FullMemory::new(8192);
+        ovl.overlap_filter().unwrap().trim = 200;
+
+        ovl.init_from_reader(Box::new(M4_FILE), "m4", Some(util::FileType::M4))
+            .expect("Error in overlap init");
+
+        assert_eq!(vec![(20, 4300), (5700, 9800)], ovl.overlap("1").unwrap());
+        assert_eq!(vec![(5700, 10000)], ovl.overlap("2").unwrap());
+        assert_eq!(vec![(0, 4300)], ovl.overlap("3").unwrap());
+    }
+
+    #[test]
+    fn trim_one_side() {
+        let mut ovl = FullMemory::new(8192);
+        ovl.overlap_filter().unwrap().trim = 200;
+
+        ovl.add_overlap_pair_cigar(
+            OverlapPair {
+                id_a: "1".to_string(),
+                ovl_a: (1000, 4000),
+                len_a: 10000,
+                id_b: "2".to_string(),
+                ovl_b: (1000, 1300),
+                len_b: 10000,
+                reverse: false,
+            },
+            None,
+        )
+        .expect("Error in add overlap");
+
+        assert_eq!(vec![(1200, 3800)], ovl.overlap("1").unwrap());
+        assert_eq!(Vec::<(u32, u32)>::new(), ovl.overlap("2").unwrap());
+    }
 }
@@ -20,6 +20,8 @@ OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
 SOFTWARE.
  */
 
+//! Storage of overlaps on disk, to reduce memory usage
+
 /* crate use */
 use anyhow::{anyhow, Context, Result};
 use log::info;
@@ -27,7 +29,9 @@ use log::info;
 /* local use */
 use crate::error;
 use crate::reads2ovl;
+use crate::util;
 
+/// Keep overlaps in a database on disk, only `buffer_size` overlaps are keep in memory
 pub struct OnDisk {
     reads2ovl: rustc_hash::FxHashMap<String, Vec<(u32, u32)>>,
     reads2len: rustc_hash::FxHashMap<String, usize>,
@@ -35,15 +39,11 @@ pub struct OnDisk {
     number_of_value: u64,
     buffer_size: u64,
     read_buffer_size: usize,
-}
-
-#[derive(Debug, Clone, serde::Deserialize)]
-struct OnDiskRecord {
-    _begin: u32,
-    _end: u32,
+    overlap_filter: reads2ovl::OverlapFilter,
 }
 
 impl OnDisk {
+    /// Create a new empty storage, database is stored in `on_disk_path`
     pub fn new(on_disk_path: String, buffer_size: u64, read_buffer_size: usize) -> Self {
         let path = std::path::PathBuf::from(on_disk_path.clone());
 
@@ -66,6 +66,7 @@ impl OnDisk {
             number_of_value: 0,
             buffer_size,
             read_buffer_size,
+            overlap_filter: reads2ovl::OverlapFilter::default(),
         }
     }
 
@@ -124,8 +125,13 @@ impl OnDisk {
 }
 
 impl reads2ovl::Reads2Ovl for OnDisk {
-    fn init(&mut self, filename: &str) -> Result<()> {
-        self.sub_init(filename)?;
+    fn init_from_reader(
+        &mut self,
+        input: Box<dyn std::io::BufRead>,
+        filename: &str,
+        format: Option<util::FileType>,
+    ) -> Result<()> {
+        self.sub_init(input, filename, format)?;
 
         self.clean_buffer()
             .with_context(|| anyhow!("Error durring creation of tempory file"))?;
@@ -191,6 +197,10 @@ impl reads2ovl::Reads2Ovl for OnDisk {
         self.reads2len.keys().map(|x| x.to_string()).collect()
     }
 
+    fn overlap_filter(&mut self) -> Option<&mut reads2ovl::OverlapFilter> {
+        Some(&mut self.overlap_filter)
+    }
+
     fn read_buffer_size(&self) -> usize {
         self.read_buffer_size
     }
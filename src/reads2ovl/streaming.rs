@@ -0,0 +1,191 @@
+/*
+   Copyright (c) 2019 Pierre Marijon <pmarijon@mpi-inf.mpg.de>
+
+   Permission is hereby granted, free of charge, to any person obtaining a copy
+   of this software and associated documentation files (the "Software"), to deal
+   in the Software without restriction, including without limitation the rights
+   to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+   copies of the Software, and to permit persons to whom the Software is
+   furnished to do so, subject to the following conditions:
+
+   The above copyright notice and this permission notice shall be included in all
+   copies or substantial portions of the Software.
+
+   THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+   IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+   FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+   AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+   LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+   OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+   SOFTWARE.
+*/
+
+//! Streaming of overlaps, each read is processed as soon as all its overlaps are read
+
+/* crate use */
+use anyhow::{bail, Result};
+use log::warn;
+
+/* local use */
+use crate::error;
+use crate::reads2ovl;
+use crate::util;
+
+/// Function called on each read with its name, its overlaps and its length
+pub type ReadCallback = Box<dyn FnMut(&str, Vec<(u32, u32)>, usize) -> Result<()>>;
+
+type Block = (String, Vec<(u32, u32)>, usize);
+
+/// Keep only overlaps of current read, when overlaps of another read begin `callback` is called
+///
+/// Only query read (read a) of an overlap is used, so overlaps of a read must be contiguous
+/// in input and each overlap must be present in both directions (as query and as target).
+/// A read that reappears after its block was closed produce an error, only a hash of closed read
+/// names is kept.
+pub struct Streaming {
+    current: Option<Block>,
+    closed: rustc_hash::FxHashSet<u64>,
+    pairs: u64,
+    reverse_name_pairs: u64,
+    error: Option<anyhow::Error>,
+    callback: ReadCallback,
+    read_buffer_size: usize,
+    overlap_filter: reads2ovl::OverlapFilter,
+}
+
+impl Streaming {
+    /// Create a new streaming storage, `callback` is called on each read when its block ends
+    pub fn new(read_buffer_size: usize, callback: ReadCallback) -> Self {
+        Streaming {
+            current: None,
+            closed: rustc_hash::FxHashSet::default(),
+            pairs: 0,
+            reverse_name_pairs: 0,
+            error: None,
+            callback,
+            read_buffer_size,
+            overlap_filter: reads2ovl::OverlapFilter::default(),
+        }
+    }
+
+    /// Call `callback` on current read, must be called after last overlap
+    pub fn finish(&mut self) -> Result<()> {
+        if let Some(error) = self.error.take() {
+            return Err(error);
+        }
+
+        if let Some((id, ovls, length)) = self.current.take() {
+            self.closed.insert(reads2ovl::name_hash(&id));
+
+            (self.callback)(&id, ovls, length)?;
+        }
+
+        Ok(())
+    }
+
+    /// Return true if no overlap has a query name greater than its target name, with minimap2
+    /// `--dual=no` an overlap is written only with the smallest read name as query
+    pub fn one_direction(&self) -> bool {
+        self.pairs != 0 && self.reverse_name_pairs == 0
+    }
+
+    fn block(&mut self, id: String) -> Result<&mut Block> {
+        if self.current.as_ref().map(|x| x.0 != id).unwrap_or(true) {
+            self.finish()?;
+
+            if self.closed.contains(&reads2ovl::name_hash(&id)) {
+                bail!(error::Error::ReadNotContiguous { name: id });
+            }
+
+            self.current = Some((id, Vec::new(), 0));
+        }
+
+        Ok(self.current.as_mut().unwrap())
+    }
+}
+
+impl reads2ovl::Reads2Ovl for Streaming {
+    fn init_from_reader(
+        &mut self,
+        input: Box<dyn std::io::BufRead>,
+        filename: &str,
+        format: Option<util::FileType>,
+    ) -> Result<()> {
+        self.sub_init(input, filename, format)?;
+
+        if self.one_direction() {
+            warn!(
+                "In {}, no overlap has a query name greater than its target name, overlaps seem present in one direction only (minimap2 --dual=no), reads present only as target aren't reported",
+                filename
+            );
+        }
+
+        self.finish()
+    }
+
+    fn get_overlaps(&mut self, _new: &mut reads2ovl::MapReads2Ovl) -> bool {
+        true
+    }
+
+    fn overlap(&self, id: &str) -> Result<Vec<(u32, u32)>> {
+        match &self.current {
+            Some((current, ovls, _)) if current == id => Ok(ovls.to_vec()),
+            _ => Ok(Vec::new()),
+        }
+    }
+
+    fn length(&self, id: &str) -> usize {
+        match &self.current {
+            Some((current, _, length)) if current == id => *length,
+            _ => 0,
+        }
+    }
+
+    fn add_overlap(&mut self, id: String, ovl: (u32, u32)) -> Result<()> {
+        self.block(id)?.1.push(ovl);
+
+        Ok(())
+    }
+
+    fn add_length(&mut self, id: String, length: usize) {
+        match self.block(id) {
+            Ok(block) => block.2 = length,
+            /* error is returned by next call to finish */
+            Err(error) => self.error = Some(error),
+        }
+    }
+
+    fn add_overlap_and_length(&mut self, id: String, ovl: (u32, u32), length: usize) -> Result<()> {
+        let block = self.block(id)?;
+
+        block.1.push(ovl);
+        block.2 = length;
+
+        Ok(())
+    }
+
+    fn add_overlap_pair(&mut self, pair: reads2ovl::OverlapPair) -> Result<()> {
+        self.pairs += 1;
+        if pair.id_a > pair.id_b {
+            self.reverse_name_pairs += 1;
+        }
+
+        self.add_overlap_and_length(pair.id_a, pair.ovl_a, pair.len_a)
+    }
+
+    fn add_target_overlap(&mut self, _id: String, _ovl: (u32, u32), _length: usize) -> Result<()> {
+        Ok(())
+    }
+
+    fn get_reads(&self) -> rustc_hash::FxHashSet<String> {
+        rustc_hash::FxHashSet::default()
+    }
+
+    fn overlap_filter(&mut self) -> Option<&mut reads2ovl::OverlapFilter> {
+        Some(&mut self.overlap_filter)
+    }
+
+    fn read_buffer_size(&self) -> usize {
+        self.read_buffer_size
+    }
+}
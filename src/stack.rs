@@ -20,412 +20,2708 @@ OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
 SOFTWARE.
  */
 
-/* std use */
-use std::cmp::Reverse;
+//! Compute or read bad regions of each read
 
 /* crate use */
-use anyhow::{Context, Result};
+use anyhow::{bail, Context, Result};
+use log::warn;
 use rayon::prelude::*;
+use serde::{Deserialize, Serialize};
 
 /* local use */
+use crate::editor;
 use crate::error;
+use crate::header;
 use crate::reads2ovl;
 use crate::util;
 
+/// Provide bad regions and length of each read
 pub trait BadPart {
+    /// Compute bad regions of all reads
     fn compute_all_bad_part(&mut self);
 
+    /// Get bad regions and length of a read, a read without information have no bad region and length 0
     fn get_bad_part(&mut self, id: &str) -> Result<&(Vec<(u32, u32)>, usize)>;
 
+    /// Get name of all reads
     fn get_reads(&self) -> rustc_hash::FxHashSet<String>;
+
+    /// Return true if read is a foldback read
+    fn is_foldback(&self, _id: &str) -> bool {
+        false
+    }
+
+    /// Get repeat regions of a read, None if repeat regions aren't detected
+    fn get_repeat_part(&self, _id: &str) -> Option<&[(u32, u32)]> {
+        None
+    }
+
+    /// Get annotation of each bad region of a read, None if annotations aren't computed
+    fn get_junctions(&self, _id: &str) -> Option<&[Junction]> {
+        None
+    }
+
+    /// Get coverage statistics of a read, None if statistics aren't computed
+    fn get_read_stats(&self, _id: &str) -> Option<ReadStats> {
+        None
+    }
+
+    /// Get header lines of report, without the leading `# `
+    fn header(&self) -> Vec<String> {
+        Vec::new()
+    }
+
+    /// Get type of a read, a read with a bad region in middle is Chimeric if it isn't NotCovered or Foldback,
+    /// a read without bad region in middle and with a repeat region is Repeat
+    fn get_read_type(&mut self, id: &str, not_covered: f64) -> Result<editor::ReadType> {
+        if self.is_foldback(id) {
+            return Ok(editor::ReadType::Foldback);
+        }
+
+        let repeat = self.get_repeat_part(id).is_some_and(|r| !r.is_empty());
+        let (bads, length) = self.get_bad_part(id)?;
+
+        Ok(editor::type_of_read(*length, bads, not_covered).with_repeat(repeat))
+    }
+}
+
+/// Minimal ratio of overlaps on the main strand of each half of a foldback read
+pub(crate) const FOLDBACK_STRAND_RATIO: f64 = 0.9;
+
+/// Minimal ratio of partners shared by the two halves of a foldback read
+pub(crate) const FOLDBACK_SHARED_PARTNERS: f64 = 0.5;
+
+/// Size of windows on each side of a candidate junction used to find partners
+pub(crate) const PARTNER_FLANK: u32 = 500;
+
+/// Minimal number of partners on each side of a junction
+pub(crate) const PARTNER_MIN_SUPPORT: usize = 3;
+
+/// Maximal ratio of partners spanning a junction, on number of partners on each side
+pub(crate) const PARTNER_MAX_SPANNING: f64 = 0.1;
+
+type Modification = (String, Vec<(u32, u32)>, Option<u32>, Vec<(u32, u32)>);
+type AnnotatedRegions = (Vec<(u32, u32)>, Vec<Junction>);
+
+/// Size of windows around a junction used to compute its score
+pub(crate) const JUNCTION_WINDOW: u32 = 500;
+
+/// Length of a bad region with half of the maximal length score
+pub(crate) const JUNCTION_HALF_LENGTH: f64 = 50.0;
+
+/// Flanking coverage with half of the maximal depth score
+pub(crate) const JUNCTION_HALF_DEPTH: f64 = 2.0;
+
+/// Maximal distance between two overlap ends of the same cluster
+pub(crate) const JUNCTION_CLUSTER_GAP: u32 = 50;
+
+/// Fraction of overlap ends of a cluster included in the confidence interval of a breakpoint
+pub(crate) const JUNCTION_CONFIDENCE: f64 = 0.9;
+
+/// Annotation of a bad region in middle of a read
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct Junction {
+    /// confidence score between 0 and 1
+    pub score: Option<f64>,
+    /// confidence interval of bad region begin
+    pub begin_ci: Option<(u32, u32)>,
+    /// confidence interval of bad region end
+    pub end_ci: Option<(u32, u32)>,
+}
+
+/// Coverage statistics of a read
+#[derive(Debug, Clone, Copy, PartialEq, Default, Serialize, Deserialize)]
+pub struct ReadStats {
+    /// median coverage of read
+    pub median: u32,
+    /// a region with coverage lower or equal to this value is bad
+    pub threshold: u64,
+    /// with a relative coverage, a region with coverage lower than this value is also bad
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub relative_threshold: Option<f64>,
+    /// modal coverage of all reads, present only with an automatic threshold
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub modal: Option<u32>,
+}
+
+impl ReadStats {
+    /// Create statistics of a read with its median coverage, modal coverage isn't set
+    pub fn new(median: u32, params: &DetectionParams) -> Self {
+        ReadStats {
+            median,
+            threshold: params.coverage,
+            relative_threshold: params
+                .relative_coverage
+                .map(|fraction| median as f64 * fraction),
+            modal: None,
+        }
+    }
+}
+
+/// Coverage threshold, a fixed value or computed from modal coverage
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CoverageThreshold {
+    /// threshold is computed from modal coverage of all reads
+    Auto,
+    /// fixed threshold
+    Value(u64),
+}
+
+impl std::str::FromStr for CoverageThreshold {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "auto" => Ok(CoverageThreshold::Auto),
+            _ => s
+                .parse::<u64>()
+                .map(CoverageThreshold::Value)
+                .map_err(|_| format!("{} isn't a coverage threshold (auto|integer)", s)),
+        }
+    }
+}
+
+impl std::fmt::Display for CoverageThreshold {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            CoverageThreshold::Auto => write!(f, "auto"),
+            CoverageThreshold::Value(coverage) => write!(f, "{}", coverage),
+        }
+    }
+}
+
+/// Bad regions, repeat regions and junction annotations of a read
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct ReadParts {
+    /// bad regions
+    pub bads: Vec<(u32, u32)>,
+    /// repeat regions
+    pub repeats: Vec<(u32, u32)>,
+    /// annotation of each bad region, empty if annotations aren't computed
+    pub junctions: Vec<Junction>,
+    /// coverage profile, empty if [DetectionParams::coverage_profile] isn't set
+    pub profile: Vec<(u32, u32, u32)>,
+    /// coverage statistics, None if [DetectionParams::read_stats] isn't set
+    pub stats: Option<ReadStats>,
+}
+
+/// Parameters of bad region detection
+#[derive(Debug, Clone, PartialEq)]
+pub struct DetectionParams {
+    /// a region with coverage lower or equal to this value is bad
+    pub coverage: u64,
+    /// if set, `coverage` is replaced by this fraction of the modal coverage of all reads
+    pub auto_coverage: Option<f64>,
+    /// if set, a region with coverage lower than this fraction of the read median coverage is also bad
+    pub relative_coverage: Option<f64>,
+    /// if set, a bad region is extended on both sides while coverage is lower or equal to this value
+    pub leave_coverage: Option<u64>,
+    /// bad regions shorter than this value are ignored
+    pub min_length: u32,
+    /// bad regions separated by this distance or less are merged
+    pub merge_distance: u32,
+    /// if set, a region with coverage upper or equal to this value times the read median coverage is a repeat
+    pub repeat_coverage: Option<f64>,
+    /// detect foldback reads, require overlap details
+    pub foldback: bool,
+    /// detect junctions with partners of overlaps, require overlap details
+    pub partner_junction: bool,
+    /// compute a score for each bad region in middle of read
+    pub junction_score: bool,
+    /// refine bounds of bad regions in middle of read with clusters of overlap ends
+    pub refine_junction: bool,
+    /// keep coverage profile of each read
+    pub coverage_profile: bool,
+    /// compute coverage statistics of each read
+    pub read_stats: bool,
+}
+
+impl DetectionParams {
+    /// Create parameters with a fixed coverage threshold
+    pub fn new(coverage: u64) -> Self {
+        DetectionParams {
+            coverage,
+            auto_coverage: None,
+            relative_coverage: None,
+            leave_coverage: None,
+            min_length: 0,
+            merge_distance: 0,
+            repeat_coverage: None,
+            foldback: false,
+            partner_junction: false,
+            junction_score: false,
+            refine_junction: false,
+            coverage_profile: false,
+            read_stats: false,
+        }
+    }
+
+    /// Return true if bad regions in middle of read are annotated
+    pub fn junction_annotation(&self) -> bool {
+        self.junction_score || self.refine_junction
+    }
+
+    /// Return true if a region with this coverage is bad, `median` is the median coverage of the read
+    pub fn is_bad(&self, depth: u32, median: u32) -> bool {
+        self.below(depth, median, self.coverage)
+    }
+
+    /// Return true if a region with this coverage extends a bad region, `median` is the median coverage of the read
+    pub fn is_still_bad(&self, depth: u32, median: u32) -> bool {
+        let leave = self
+            .leave_coverage
+            .unwrap_or(self.coverage)
+            .max(self.coverage);
+
+        self.below(depth, median, leave)
+    }
+
+    fn below(&self, depth: u32, median: u32, threshold: u64) -> bool {
+        depth as u64 <= threshold
+            || self
+                .relative_coverage
+                .map(|fraction| (depth as f64) < median as f64 * fraction)
+                .unwrap_or(false)
+    }
 }
 
+/// Compute bad regions from overlaps, a region with coverage lower or equal to `coverage` is bad
+///
+/// If `ovl` keeps strand and partner of overlaps (see [reads2ovl::Reads2Ovl::keep_details]), with
+/// [DetectionParams::foldback] foldback reads are detected and the region after the fold is added
+/// to their bad regions. With
+/// [DetectionParams::partner_junction], positions where partners of left side and right side are
+/// different are also added to bad regions, even if coverage stays high.
+///
+/// With an automatic coverage threshold, a first pass computes the coverage histogram of all reads,
+/// so all overlaps are loaded in memory.
+///
+/// If [DetectionParams::repeat_coverage] is set, over-covered regions are kept as repeat regions.
 pub struct FromOverlap {
     ovl: Box<dyn reads2ovl::Reads2Ovl>,
-    coverage: u64,
+    params: DetectionParams,
+    modal_coverage: Option<u32>,
     buffer: reads2ovl::MapReads2Ovl,
+    repeats: rustc_hash::FxHashMap<String, Vec<(u32, u32)>>,
+    junctions: rustc_hash::FxHashMap<String, Vec<Junction>>,
+    medians: rustc_hash::FxHashMap<String, u32>,
+    bedgraph: Option<Box<dyn std::io::Write>>,
+    error: Option<anyhow::Error>,
+    foldbacks: rustc_hash::FxHashMap<String, u32>,
     empty: (Vec<(u32, u32)>, usize),
 }
 
 impl FromOverlap {
+    /// Create a new object, overlaps of `ovl` are consumed by [BadPart::compute_all_bad_part]
     pub fn new(ovl: Box<dyn reads2ovl::Reads2Ovl>, coverage: u64) -> Self {
+        FromOverlap::with_params(ovl, DetectionParams::new(coverage))
+    }
+
+    /// Create a new object with detection parameters
+    pub fn with_params(ovl: Box<dyn reads2ovl::Reads2Ovl>, params: DetectionParams) -> Self {
         let empty = (Vec::new(), 0);
         FromOverlap {
             ovl,
-            coverage,
+            params,
+            modal_coverage: None,
+            repeats: rustc_hash::FxHashMap::default(),
+            junctions: rustc_hash::FxHashMap::default(),
+            medians: rustc_hash::FxHashMap::default(),
+            bedgraph: None,
+            error: None,
             buffer: rustc_hash::FxHashMap::default(),
+            foldbacks: rustc_hash::FxHashMap::default(),
             empty,
         }
     }
 
-    fn compute_bad_part(mut ovls: Vec<(u32, u32)>, len: usize, coverage: usize) -> Vec<(u32, u32)> {
-        let mut gaps: Vec<(u32, u32)> = Vec::new();
-        let mut stack: std::collections::BinaryHeap<Reverse<u32>> =
-            std::collections::BinaryHeap::new();
+    /// Write coverage profile of each read in bedGraph in `out`, profiles are written after each
+    /// batch of reads and aren't kept
+    pub fn write_coverage_profile(&mut self, out: Box<dyn std::io::Write>) {
+        self.params.coverage_profile = true;
+        self.bedgraph = Some(out);
+    }
 
-        ovls.sort_unstable();
+    /// Return error of coverage profile writing, must be called after [BadPart::compute_all_bad_part]
+    pub fn finish(&mut self) -> Result<()> {
+        if let Some(error) = self.error.take() {
+            return Err(error);
+        }
 
-        let mut first_covered = 0;
-        let mut last_covered = 0;
+        if let Some(out) = self.bedgraph.as_mut() {
+            out.flush()
+                .with_context(|| error::Error::WritingErrorNoFilename {
+                    format: util::FileType::Yacrd,
+                })?;
+        }
 
-        for interval in ovls {
-            while let Some(head) = stack.peek() {
-                if head.0 > interval.0 {
-                    break;
+        Ok(())
+    }
+
+    /// Get coverage threshold, computed by [BadPart::compute_all_bad_part] with an automatic threshold
+    pub fn coverage(&self) -> u64 {
+        self.params.coverage
+    }
+
+    /// Get modal coverage of all reads, available only with an automatic threshold
+    pub fn modal_coverage(&self) -> Option<u32> {
+        self.modal_coverage
+    }
+
+    /// Compute number of bases covered by each coverage value, index is coverage
+    pub(crate) fn coverage_histogram(reads2ovl: &reads2ovl::MapReads2Ovl) -> Vec<u64> {
+        reads2ovl
+            .par_iter()
+            .map(|(_, (ovls, len))| {
+                let mut histogram = Vec::new();
+                for (begin, end, depth) in FromOverlap::coverage_profile(ovls, *len) {
+                    if histogram.len() <= depth as usize {
+                        histogram.resize(depth as usize + 1, 0);
+                    }
+                    histogram[depth as usize] += (end - begin) as u64;
                 }
+                histogram
+            })
+            .reduce(Vec::new, |mut a, b| {
+                if a.len() < b.len() {
+                    a.resize(b.len(), 0);
+                }
+                a.iter_mut().zip(b).for_each(|(x, y)| *x += y);
+                a
+            })
+    }
 
-                if stack.len() > coverage {
-                    last_covered = head.0;
+    /// Get the most frequent coverage value in histogram, coverage 0 is ignored
+    pub(crate) fn modal(histogram: &[u64]) -> u32 {
+        histogram
+            .iter()
+            .enumerate()
+            .skip(1)
+            .max_by_key(|(depth, count)| (**count, std::cmp::Reverse(*depth)))
+            .map(|(depth, _)| depth as u32)
+            .unwrap_or(0)
+    }
+
+    fn compute_batch(&mut self, batch: &mut reads2ovl::MapReads2Ovl) {
+        let params = &self.params;
+
+        let mut parts = batch
+            .drain()
+            .par_bridge()
+            .map(|(k, v)| (k, FromOverlap::compute_parts(v.0, v.1, params), v.1))
+            .collect::<Vec<_>>();
+
+        if let Some(out) = self.bedgraph.as_mut() {
+            /* reads of a batch are written in lexicographic order */
+            parts.sort_unstable_by(|a, b| a.0.cmp(&b.0));
+
+            for (k, parts, _) in parts.iter() {
+                if self.error.is_some() {
+                    break;
                 }
-                stack.pop();
-            }
 
-            if stack.len() <= coverage {
-                if last_covered != 0 {
-                    gaps.push((last_covered, interval.0));
-                } else {
-                    first_covered = interval.0;
+                if let Err(error) = editor::bedgraph(k, &parts.profile, out) {
+                    /* error is returned by next call to finish */
+                    self.error = Some(error);
                 }
             }
-            stack.push(Reverse(interval.1));
         }
 
-        while stack.len() > coverage {
-            last_covered = stack
-                .peek()
-                .with_context(|| error::Error::NotReachableCode {
-                    name: format!("{} {}", file!(), line!()),
-                })
-                .unwrap()
-                .0;
-            if last_covered as usize >= len {
-                break;
+        for (k, parts, len) in parts {
+            if !parts.repeats.is_empty() {
+                self.repeats.insert(k.clone(), parts.repeats);
             }
-            stack.pop();
+            if !parts.junctions.is_empty() {
+                self.junctions.insert(k.clone(), parts.junctions);
+            }
+            if let Some(stats) = parts.stats {
+                self.medians.insert(k.clone(), stats.median);
+            }
+            self.buffer.insert(k, (parts.bads, len));
         }
+    }
 
-        if first_covered != 0 {
-            gaps.insert(0, (0, first_covered));
+    /// Get position of fold of a foldback read
+    pub fn foldback_position(&self, id: &str) -> Option<u32> {
+        self.foldbacks.get(id).cloned()
+    }
+
+    /// Compute position of fold if read is a foldback read, each half of read must be supported by
+    /// overlaps on one strand, other strand for other half, with same partners
+    pub(crate) fn compute_foldback(
+        details: &[reads2ovl::OverlapDetail],
+        len: usize,
+    ) -> Option<u32> {
+        let middle = (len / 2) as u32;
+
+        let (left, right): (
+            Vec<&reads2ovl::OverlapDetail>,
+            Vec<&reads2ovl::OverlapDetail>,
+        ) = details
+            .iter()
+            .partition(|d| d.interval.0 / 2 + d.interval.1 / 2 < middle);
+
+        let strand = |half: &[&reads2ovl::OverlapDetail]| -> Option<bool> {
+            if half.is_empty() {
+                return None;
+            }
+
+            let reverse = half.iter().filter(|d| d.reverse).count() as f64 / half.len() as f64;
+            if reverse >= FOLDBACK_STRAND_RATIO {
+                Some(true)
+            } else if 1.0 - reverse >= FOLDBACK_STRAND_RATIO {
+                Some(false)
+            } else {
+                None
+            }
+        };
+
+        let left_strand = strand(&left)?;
+        let right_strand = strand(&right)?;
+        if left_strand == right_strand {
+            return None;
         }
 
-        if last_covered as usize != len {
-            gaps.push((last_covered, len as u32));
+        let left_partners: rustc_hash::FxHashSet<u64> = left
+            .iter()
+            .filter(|d| d.reverse == left_strand)
+            .map(|d| d.partner)
+            .collect();
+        let right_partners: rustc_hash::FxHashSet<u64> = right
+            .iter()
+            .filter(|d| d.reverse == right_strand)
+            .map(|d| d.partner)
+            .collect();
+
+        let shared = left_partners.intersection(&right_partners).count();
+        if (shared as f64)
+            < left_partners.len().min(right_partners.len()) as f64 * FOLDBACK_SHARED_PARTNERS
+        {
+            return None;
         }
 
-        if gaps.is_empty() {
-            return gaps;
+        let left_end = left.iter().map(|d| d.interval.1).max()?;
+        let right_begin = right.iter().map(|d| d.interval.0).min()?;
+
+        Some(left_end / 2 + right_begin / 2)
+    }
+
+    /// Compute junctions of a read, positions with partners stopping in a window around position on each
+    /// side but almost no partner spanning the window, close junction positions are merged in one region
+    ///
+    /// Each overlap stops on left side, stops on right side or spans the window for a contiguous
+    /// range of positions, so partners of each position are counted with a sweep over range ends.
+    pub(crate) fn compute_partner_junctions(
+        details: &[reads2ovl::OverlapDetail],
+        len: usize,
+    ) -> Vec<(u32, u32)> {
+        let len = len as u32;
+
+        let mut candidates = details
+            .iter()
+            .flat_map(|d| [d.interval.0, d.interval.1])
+            .filter(|pos| *pos >= PARTNER_FLANK && *pos + PARTNER_FLANK <= len)
+            .collect::<Vec<u32>>();
+        candidates.sort_unstable();
+        candidates.dedup();
+
+        /* (position, side, partner, delta), side 0 is left, 1 is right and 2 is spanning */
+        let flank = PARTNER_FLANK as i64;
+        let mut events: Vec<(i64, usize, u64, i32)> = Vec::with_capacity(details.len() * 6);
+        for detail in details {
+            let (begin, end) = (detail.interval.0 as i64, detail.interval.1 as i64);
+            let ranges = [
+                (begin.max(end - 2 * flank) + 1 + flank, end + flank),
+                (begin - flank + 1, begin.min(end - 2 * flank) + flank),
+                (begin + flank, end - flank + 1),
+            ];
+
+            for (side, (first, last)) in ranges.into_iter().enumerate() {
+                if first < last {
+                    events.push((first, side, detail.partner, 1));
+                    events.push((last, side, detail.partner, -1));
+                }
+            }
         }
+        events.sort_unstable();
+
+        let mut partners: [rustc_hash::FxHashMap<u64, i32>; 3] = Default::default();
+        let mut events = events.into_iter().peekable();
+        let mut junctions: Vec<(u32, u32)> = Vec::new();
+        for pos in candidates {
+            while let Some((_, side, partner, delta)) = events.next_if(|e| e.0 <= pos as i64) {
+                let count = partners[side].entry(partner).or_insert(0);
+                *count += delta;
+                if *count == 0 {
+                    partners[side].remove(&partner);
+                }
+            }
 
-        /* clean overlapped bad region */
-        let mut clean_gaps: Vec<(u32, u32)> = Vec::new();
-        let mut begin = gaps[0].0;
-        let mut end = gaps[0].1;
-        for gaps in gaps.windows(2) {
-            let g1 = gaps[0];
-            let g2 = gaps[1];
-
-            if g1.0 == g2.0 {
-                begin = g1.0;
-                end = g1.1.max(g2.1);
-            } else {
-                clean_gaps.push((begin, end));
-                begin = g2.0;
-                end = g2.1;
+            let support = partners[0].len().min(partners[1].len());
+            if support < PARTNER_MIN_SUPPORT
+                || partners[2].len() as f64 > support as f64 * PARTNER_MAX_SPANNING
+            {
+                continue;
+            }
+
+            match junctions.last_mut() {
+                Some(last) if pos - last.1 <= PARTNER_FLANK => last.1 = pos,
+                _ => junctions.push((pos, pos)),
             }
         }
-        clean_gaps.push((begin, end));
 
-        clean_gaps
+        for junction in junctions.iter_mut() {
+            junction.1 = junction.1.max(junction.0 + 1);
+        }
+
+        junctions
     }
-}
 
-impl BadPart for FromOverlap {
-    fn compute_all_bad_part(&mut self) {
-        let mut new = rustc_hash::FxHashMap::default();
+    fn add_junctions(&mut self, id: &str, junctions: Vec<(u32, u32)>) {
+        if let Some((bads, _)) = self.buffer.get_mut(id) {
+            bads.extend(junctions);
+            bads.sort_unstable();
 
-        let coverage = self.coverage as usize;
+            let mut merged: Vec<(u32, u32)> = Vec::with_capacity(bads.len());
+            for bad in bads.drain(..) {
+                match merged.last_mut() {
+                    Some(last) if bad.0 <= last.1 => last.1 = last.1.max(bad.1),
+                    _ => merged.push(bad),
+                }
+            }
 
-        loop {
-            let finish = self.ovl.get_overlaps(&mut new);
+            *bads = merged;
+        }
+    }
 
-            self.buffer.extend(
-                new.drain()
-                    .par_bridge()
-                    .map(|(k, v)| (k, (FromOverlap::compute_bad_part(v.0, v.1, coverage), v.1)))
-                    .collect::<reads2ovl::MapReads2Ovl>(),
-            );
+    fn add_foldback(&mut self, id: String, fold: u32) {
+        if let Some((bads, len)) = self.buffer.get_mut(&id) {
+            let begin = bads
+                .iter()
+                .filter(|b| b.1 >= fold)
+                .map(|b| b.0)
+                .min()
+                .unwrap_or(fold)
+                .min(fold);
+
+            bads.retain(|b| b.1 < fold);
+            bads.push((begin, *len as u32));
+        }
 
-            if finish {
-                break;
+        self.foldbacks.insert(id, fold);
+    }
+
+    /// Compute coverage of a read with its overlaps, return contiguous regions (begin, end, coverage) covering the whole read
+    ///
+    /// Unlike a scan that only tracks whether coverage is above the threshold, the profile gives
+    /// coverage of each base, it's needed by the coverage histogram, relative thresholds, hysteresis
+    /// and repeat detection.
+    pub(crate) fn coverage_profile(ovls: &[(u32, u32)], len: usize) -> Vec<(u32, u32, u32)> {
+        let len = len as u32;
+
+        let mut events: Vec<(u32, i64)> = Vec::with_capacity(ovls.len() * 2);
+        for (begin, end) in ovls {
+            let (begin, end) = ((*begin).min(len), (*end).min(len));
+            if begin < end {
+                events.push((begin, 1));
+                events.push((end, -1));
+            }
+        }
+        events.sort_unstable();
+
+        let mut profile: Vec<(u32, u32, u32)> = Vec::new();
+        let mut depth: i64 = 0;
+        let mut prev = 0;
+        for (pos, delta) in events.into_iter().chain(std::iter::once((len, 0))) {
+            if pos > prev {
+                match profile.last_mut() {
+                    Some(last) if last.2 == depth as u32 => last.1 = pos,
+                    _ => profile.push((prev, pos, depth as u32)),
+                }
+                prev = pos;
             }
+            depth += delta;
         }
+
+        profile
     }
 
-    fn get_bad_part(&mut self, id: &str) -> Result<&(Vec<(u32, u32)>, usize)> {
-        match self.buffer.get(id) {
-            Some(v) => Ok(v),
-            None => Ok(&self.empty),
+    /// Compute median coverage of a read from its coverage profile
+    pub(crate) fn median_coverage(profile: &[(u32, u32, u32)]) -> u32 {
+        let mut depths = profile
+            .iter()
+            .map(|(begin, end, depth)| (*depth, end - begin))
+            .collect::<Vec<(u32, u32)>>();
+        depths.sort_unstable();
+
+        let half = depths
+            .iter()
+            .map(|(_, len)| *len as u64)
+            .sum::<u64>()
+            .div_ceil(2);
+        let mut covered = 0;
+        for (depth, len) in depths {
+            covered += len as u64;
+            if covered >= half {
+                return depth;
+            }
         }
+
+        0
     }
 
-    fn get_reads(&self) -> rustc_hash::FxHashSet<String> {
-        self.buffer.keys().map(|x| x.to_string()).collect()
+    /// Compute bad regions of a read with its overlaps and its length
+    ///
+    /// A region where coverage is bad is extended on both sides while coverage is still bad (see
+    /// [DetectionParams::is_still_bad]), close bad regions are merged and short ones are ignored.
+    pub fn compute_bad_part(
+        ovls: Vec<(u32, u32)>,
+        len: usize,
+        params: &DetectionParams,
+    ) -> Vec<(u32, u32)> {
+        FromOverlap::compute_parts(ovls, len, params).bads
     }
-}
 
-pub struct FromReport {
-    buffer: reads2ovl::MapReads2Ovl,
-    empty: (Vec<(u32, u32)>, usize),
-}
+    /// Compute bad regions, repeat regions and junction annotations of a read with its overlaps and its length
+    pub fn compute_parts(ovls: Vec<(u32, u32)>, len: usize, params: &DetectionParams) -> ReadParts {
+        let profile = FromOverlap::coverage_profile(&ovls, len);
+        let median = if params.relative_coverage.is_some()
+            || params.repeat_coverage.is_some()
+            || params.read_stats
+        {
+            FromOverlap::median_coverage(&profile)
+        } else {
+            0
+        };
+
+        let repeats = match params.repeat_coverage {
+            Some(factor) => FromOverlap::repeat_part(&profile, median, factor),
+            None => Vec::new(),
+        };
+
+        let mut bads = FromOverlap::bad_part(&profile, median, params);
+        let junctions = FromOverlap::annotate_junctions(&ovls, &profile, &mut bads, len, params);
+
+        ReadParts {
+            bads,
+            repeats,
+            junctions,
+            profile: if params.coverage_profile {
+                profile
+            } else {
+                Vec::new()
+            },
+            stats: params.read_stats.then(|| ReadStats::new(median, params)),
+        }
+    }
 
-impl FromReport {
-    pub fn new(input_path: &str) -> Result<Self> {
-        let input =
-            std::io::BufReader::new(std::fs::File::open(input_path).with_context(|| {
-                error::Error::CantReadFile {
-                    filename: input_path.to_string(),
-                }
-            })?);
-        let mut reader = csv::ReaderBuilder::new()
-            .delimiter(b'\t')
-            .has_headers(false)
-            .from_reader(input);
+    /// Compute annotation of each bad region, with [DetectionParams::refine_junction] bounds of bad
+    /// regions in middle of read are replaced by consensus breakpoints, then refined regions are
+    /// merged and filtered again, scores are computed on final regions
+    pub(crate) fn annotate_junctions(
+        ovls: &[(u32, u32)],
+        profile: &[(u32, u32, u32)],
+        bads: &mut Vec<(u32, u32)>,
+        len: usize,
+        params: &DetectionParams,
+    ) -> Vec<Junction> {
+        if !params.junction_annotation() {
+            return Vec::new();
+        }
 
-        let mut buffer = rustc_hash::FxHashMap::default();
-        for (line, record) in reader.records().enumerate() {
-            let result = record.with_context(|| error::Error::Reading {
-                filename: input_path.to_string(),
-                format: util::FileType::Fasta,
-            })?;
+        let mut junctions = vec![Junction::default(); bads.len()];
 
-            let id = result[1].to_string();
-            let len = util::str2usize(&result[2])?;
-            let bad_part = FromReport::parse_bad_string(&result[3]).with_context(|| {
-                error::Error::CorruptYacrdReport {
-                    name: input_path.to_string(),
-                    line,
-                }
-            })?;
+        if params.refine_junction {
+            FromOverlap::refine_junctions(ovls, bads, &mut junctions, len);
+            FromOverlap::merge_refined(bads, &mut junctions, params);
+        }
 
-            buffer.insert(id, (bad_part, len));
+        if params.junction_score {
+            let scores = FromOverlap::junction_scores(ovls, profile, bads, len);
+            for (junction, score) in junctions.iter_mut().zip(scores) {
+                junction.score = score;
+            }
         }
 
-        let empty = (Vec::new(), 0);
-        Ok(FromReport { buffer, empty })
+        junctions
     }
 
-    fn parse_bad_string(bad_string: &str) -> Result<Vec<(u32, u32)>> {
-        let mut ret = Vec::new();
+    /// Sort refined bad regions, merge close ones like [FromOverlap::compute_bad_part] and remove short ones,
+    /// a merged region keeps begin confidence interval of its first region and end confidence interval of its last
+    fn merge_refined(
+        bads: &mut Vec<(u32, u32)>,
+        junctions: &mut Vec<Junction>,
+        params: &DetectionParams,
+    ) {
+        let mut regions = bads
+            .drain(..)
+            .zip(junctions.drain(..))
+            .collect::<Vec<((u32, u32), Junction)>>();
+        regions.sort_unstable_by_key(|(bad, _)| *bad);
+
+        let mut merged: Vec<((u32, u32), Junction)> = Vec::with_capacity(regions.len());
+        for (bad, junction) in regions {
+            match merged.last_mut() {
+                Some((last, last_junction))
+                    if bad.0.saturating_sub(last.1) <= params.merge_distance =>
+                {
+                    if bad.1 > last.1 {
+                        last.1 = bad.1;
+                        last_junction.end_ci = junction.end_ci;
+                    }
+                }
+                _ => merged.push((bad, junction)),
+            }
+        }
 
-        if bad_string.is_empty() {
-            return Ok(ret);
+        for (bad, junction) in merged {
+            if bad.1 - bad.0 >= params.min_length {
+                bads.push(bad);
+                junctions.push(junction);
+            }
         }
+    }
 
-        for sub in bad_string.split(';') {
-            let mut iter = sub.split(',');
-            iter.next();
+    /// Refine bounds of bad regions in middle of read, ends of overlaps stopping on left side near
+    /// region begin (and begins of overlaps starting on right side near region end) are clustered,
+    /// median of the largest cluster is the consensus breakpoint
+    pub(crate) fn refine_junctions(
+        ovls: &[(u32, u32)],
+        bads: &mut [(u32, u32)],
+        junctions: &mut [Junction],
+        len: usize,
+    ) {
+        for (bad, junction) in bads.iter_mut().zip(junctions.iter_mut()) {
+            if bad.0 == 0 || bad.1 as usize >= len {
+                continue;
+            }
 
-            ret.push((
-                util::str2u32(
-                    iter.next()
-                        .with_context(|| error::Error::CorruptYacrdReportInPosition)?,
-                )?,
-                util::str2u32(
-                    iter.next()
-                        .with_context(|| error::Error::CorruptYacrdReportInPosition)?,
-                )?,
-            ));
-        }
+            let ends = ovls
+                .iter()
+                .filter(|(b, e)| *b < bad.0 && e.abs_diff(bad.0) <= JUNCTION_WINDOW)
+                .map(|(_, e)| *e)
+                .collect();
+            let begins = ovls
+                .iter()
+                .filter(|(b, e)| *e > bad.1 && b.abs_diff(bad.1) <= JUNCTION_WINDOW)
+                .map(|(b, _)| *b)
+                .collect();
+
+            let (begin, begin_ci) =
+                FromOverlap::breakpoint(ends).unwrap_or((bad.0, (bad.0, bad.0)));
+            let (end, end_ci) = FromOverlap::breakpoint(begins).unwrap_or((bad.1, (bad.1, bad.1)));
 
-        Ok(ret)
+            *bad = if begin < end {
+                (begin, end)
+            } else {
+                let middle = begin / 2 + end / 2;
+                (middle, middle + 1)
+            };
+            junction.begin_ci = Some(begin_ci);
+            junction.end_ci = Some(end_ci);
+        }
     }
-}
 
-impl BadPart for FromReport {
-    fn compute_all_bad_part(&mut self) {}
+    /// Get consensus position and confidence interval of the largest cluster of positions
+    pub(crate) fn breakpoint(mut positions: Vec<u32>) -> Option<(u32, (u32, u32))> {
+        positions.sort_unstable();
 
-    fn get_bad_part(&mut self, id: &str) -> Result<&(Vec<(u32, u32)>, usize)> {
-        match self.buffer.get(id) {
-            Some(v) => Ok(v),
-            None => Ok(&self.empty),
+        let mut clusters: Vec<&[u32]> = Vec::new();
+        let mut first = 0;
+        for i in 1..=positions.len() {
+            if i == positions.len() || positions[i] - positions[i - 1] > JUNCTION_CLUSTER_GAP {
+                clusters.push(&positions[first..i]);
+                first = i;
+            }
         }
+
+        let cluster = clusters.into_iter().max_by_key(|cluster| cluster.len())?;
+
+        let quantile = |q: f64| cluster[((cluster.len() - 1) as f64 * q).round() as usize];
+        let margin = (1.0 - JUNCTION_CONFIDENCE) / 2.0;
+
+        Some((
+            cluster[cluster.len() / 2],
+            (quantile(margin), quantile(1.0 - margin)),
+        ))
     }
 
-    fn get_reads(&self) -> rustc_hash::FxHashSet<String> {
-        self.buffer.keys().map(|x| x.to_string()).collect()
+    /// Compute score of each bad region, bad regions at read end have no score
+    pub(crate) fn junction_scores(
+        ovls: &[(u32, u32)],
+        profile: &[(u32, u32, u32)],
+        bads: &[(u32, u32)],
+        len: usize,
+    ) -> Vec<Option<f64>> {
+        bads.iter()
+            .map(|bad| {
+                if bad.0 == 0 || bad.1 as usize >= len {
+                    None
+                } else {
+                    Some(FromOverlap::junction_score(ovls, profile, *bad))
+                }
+            })
+            .collect()
     }
-}
 
-#[cfg(test)]
-mod tests {
-    use super::*;
+    /// Compute score of a bad region in middle of a read, between 0 and 1
+    ///
+    /// Score grows with the coverage of windows flanking the region, the coverage drop inside the
+    /// region (min and mean coverage), the number of overlaps ending in windows around region
+    /// bounds and the region length.
+    pub(crate) fn junction_score(
+        ovls: &[(u32, u32)],
+        profile: &[(u32, u32, u32)],
+        region: (u32, u32),
+    ) -> f64 {
+        let (begin, end) = region;
+        let len = profile.last().map(|p| p.1).unwrap_or(0);
+
+        let left =
+            FromOverlap::coverage_stats(profile, begin.saturating_sub(JUNCTION_WINDOW), begin);
+        let right = FromOverlap::coverage_stats(profile, end, (end + JUNCTION_WINDOW).min(len));
+        let inside = FromOverlap::coverage_stats(profile, begin, end);
+
+        let flank = left.1.min(right.1);
+        if flank <= 0.0 {
+            return 0.0;
+        }
 
-    use std::io::Write;
+        let drop = (1.0 - (inside.0 as f64 + inside.1) / (2.0 * flank)).clamp(0.0, 1.0);
 
-    extern crate tempfile;
-    use self::tempfile::NamedTempFile;
+        let near = |pos: u32, bound: u32| pos.abs_diff(bound) <= JUNCTION_WINDOW;
+        let ends = ovls
+            .iter()
+            .filter(|(b, e)| near(*e, begin) || near(*b, end))
+            .count();
+        let ends = (ends as f64 / (2.0 * flank)).min(1.0);
 
-    use reads2ovl::Reads2Ovl;
+        let length = (end - begin) as f64;
+        let length = length / (length + JUNCTION_HALF_LENGTH);
 
-    #[test]
-    fn from_report() {
-        let mut report = NamedTempFile::new().expect("Can't create tmpfile");
+        let depth = flank / (flank + JUNCTION_HALF_DEPTH);
 
-        writeln!(
-            report.as_file_mut(),
+        depth * (drop + ends + length) / 3.0
+    }
+
+    /// Get min and mean coverage between `begin` and `end` in profile
+    fn coverage_stats(profile: &[(u32, u32, u32)], begin: u32, end: u32) -> (u32, f64) {
+        let mut min = u32::MAX;
+        let mut sum = 0;
+        for (b, e, depth) in profile {
+            let (b, e) = ((*b).max(begin), (*e).min(end));
+            if b < e {
+                min = min.min(*depth);
+                sum += (e - b) as u64 * *depth as u64;
+            }
+        }
+
+        if end <= begin || min == u32::MAX {
+            (0, 0.0)
+        } else {
+            (min, sum as f64 / (end - begin) as f64)
+        }
+    }
+
+    /// Get regions of profile with coverage upper or equal to `factor` times `median`
+    pub(crate) fn repeat_part(
+        profile: &[(u32, u32, u32)],
+        median: u32,
+        factor: f64,
+    ) -> Vec<(u32, u32)> {
+        let mut repeats: Vec<(u32, u32)> = Vec::new();
+
+        if median == 0 {
+            return repeats;
+        }
+
+        for (begin, end, depth) in profile {
+            if (*depth as f64) < median as f64 * factor {
+                continue;
+            }
+
+            match repeats.last_mut() {
+                Some(last) if last.1 == *begin => last.1 = *end,
+                _ => repeats.push((*begin, *end)),
+            }
+        }
+
+        repeats
+    }
+
+    fn bad_part(
+        profile: &[(u32, u32, u32)],
+        median: u32,
+        params: &DetectionParams,
+    ) -> Vec<(u32, u32)> {
+        let mut gaps: Vec<(u32, u32, bool)> = Vec::new();
+
+        for &(begin, end, depth) in profile {
+            if !params.is_still_bad(depth, median) {
+                continue;
+            }
+
+            let bad = params.is_bad(depth, median);
+            match gaps.last_mut() {
+                Some(last) if last.1 == begin => {
+                    last.1 = end;
+                    last.2 |= bad;
+                }
+                _ => gaps.push((begin, end, bad)),
+            }
+        }
+
+        let mut merged: Vec<(u32, u32)> = Vec::new();
+        for (begin, end, _) in gaps.into_iter().filter(|gap| gap.2) {
+            match merged.last_mut() {
+                Some(last) if begin - last.1 <= params.merge_distance => last.1 = end,
+                _ => merged.push((begin, end)),
+            }
+        }
+
+        merged.retain(|(begin, end)| end - begin >= params.min_length);
+
+        merged
+    }
+}
+
+impl BadPart for FromOverlap {
+    fn compute_all_bad_part(&mut self) {
+        let mut new = rustc_hash::FxHashMap::default();
+
+        if let Some(fraction) = self.params.auto_coverage {
+            let mut all = rustc_hash::FxHashMap::default();
+            loop {
+                let finish = self.ovl.get_overlaps(&mut new);
+                all.extend(new.drain());
+
+                if finish {
+                    break;
+                }
+            }
+
+            let modal = FromOverlap::modal(&FromOverlap::coverage_histogram(&all));
+            self.modal_coverage = Some(modal);
+            self.params.coverage = (modal as f64 * fraction) as u64;
+            warn!(
+                "Coverage threshold {} is {} of modal coverage {}",
+                self.params.coverage, fraction, modal
+            );
+
+            self.compute_batch(&mut all);
+        } else {
+            loop {
+                let finish = self.ovl.get_overlaps(&mut new);
+
+                self.compute_batch(&mut new);
+
+                if finish {
+                    break;
+                }
+            }
+        }
+
+        let mut details = rustc_hash::FxHashMap::default();
+        self.ovl.get_details(&mut details);
+
+        let buffer = &self.buffer;
+        let params = &self.params;
+        let results = details
+            .into_par_iter()
+            .filter_map(|(k, v)| {
+                let len = buffer.get(&k)?.1;
+
+                let junctions = if params.partner_junction {
+                    FromOverlap::compute_partner_junctions(&v, len)
+                } else {
+                    Vec::new()
+                };
+                let fold = if params.foldback {
+                    FromOverlap::compute_foldback(&v, len)
+                } else {
+                    None
+                };
+
+                let intervals =
+                    if params.junction_annotation() && (!junctions.is_empty() || fold.is_some()) {
+                        v.iter().map(|d| d.interval).collect()
+                    } else {
+                        Vec::new()
+                    };
+
+                Some((k, junctions, fold, intervals))
+            })
+            .collect::<Vec<Modification>>();
+
+        for (id, junctions, fold, intervals) in results {
+            if !junctions.is_empty() {
+                self.add_junctions(&id, junctions);
+            }
+            if let Some(fold) = fold {
+                self.add_foldback(id.clone(), fold);
+            }
+
+            /* bad regions changed, annotations are computed again */
+            if !intervals.is_empty() {
+                if let Some((bads, len)) = self.buffer.get_mut(&id) {
+                    let profile = FromOverlap::coverage_profile(&intervals, *len);
+                    let junctions = FromOverlap::annotate_junctions(
+                        &intervals,
+                        &profile,
+                        bads,
+                        *len,
+                        &self.params,
+                    );
+                    self.junctions.insert(id, junctions);
+                }
+            }
+        }
+    }
+
+    fn get_bad_part(&mut self, id: &str) -> Result<&(Vec<(u32, u32)>, usize)> {
+        match self.buffer.get(id) {
+            Some(v) => Ok(v),
+            None => Ok(&self.empty),
+        }
+    }
+
+    fn get_reads(&self) -> rustc_hash::FxHashSet<String> {
+        self.buffer.keys().map(|x| x.to_string()).collect()
+    }
+
+    fn is_foldback(&self, id: &str) -> bool {
+        self.foldbacks.contains_key(id)
+    }
+
+    fn get_repeat_part(&self, id: &str) -> Option<&[(u32, u32)]> {
+        self.params.repeat_coverage?;
+
+        Some(self.repeats.get(id).map(Vec::as_slice).unwrap_or(&[]))
+    }
+
+    fn get_junctions(&self, id: &str) -> Option<&[Junction]> {
+        if !self.params.junction_annotation() {
+            return None;
+        }
+
+        Some(self.junctions.get(id).map(Vec::as_slice).unwrap_or(&[]))
+    }
+
+    fn get_read_stats(&self, id: &str) -> Option<ReadStats> {
+        if !self.params.read_stats {
+            return None;
+        }
+
+        let median = self.medians.get(id).cloned().unwrap_or(0);
+        Some(ReadStats {
+            modal: self.modal_coverage,
+            ..ReadStats::new(median, &self.params)
+        })
+    }
+
+    fn header(&self) -> Vec<String> {
+        match (self.modal_coverage, self.params.auto_coverage) {
+            (Some(modal), Some(fraction)) => vec![format!(
+                "coverage threshold: {} (auto, {} of modal coverage {})",
+                self.params.coverage, fraction, modal
+            )],
+            _ => Vec::new(),
+        }
+    }
+}
+
+/// A line of jsonl report, the provenance header, a trailing input or a read
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum JsonLine {
+    Header { yacrd_header: header::ReportHeader },
+    Input { yacrd_input: header::Input },
+    Read(editor::JsonRead),
+}
+
+/// Read bad regions from a yacrd report, repeat regions are read in the fifth column if it's present
+/// and junction annotations in bad regions if they are present, coverage statistics of reads are
+/// read in jsonl report
+pub struct FromReport {
+    buffer: reads2ovl::MapReads2Ovl,
+    foldbacks: rustc_hash::FxHashSet<String>,
+    repeats: Option<rustc_hash::FxHashMap<String, Vec<(u32, u32)>>>,
+    junctions: Option<rustc_hash::FxHashMap<String, Vec<Junction>>>,
+    stats: rustc_hash::FxHashMap<String, ReadStats>,
+    report_header: Option<header::ReportHeader>,
+    empty: (Vec<(u32, u32)>, usize),
+}
+
+impl FromReport {
+    /// Read all bad regions present in report, compression is autodetected
+    pub fn new(input_path: &str) -> Result<Self> {
+        let (input, _) = util::read_file(input_path, 8192)?;
+
+        FromReport::from_reader(input, input_path)
+    }
+
+    /// Read all bad regions present in an opened report, `input_path` is used in error message.
+    /// Report can be a yacrd report or a jsonl report.
+    pub fn from_reader(mut input: Box<dyn std::io::BufRead>, input_path: &str) -> Result<Self> {
+        let mut report = FromReport {
+            buffer: rustc_hash::FxHashMap::default(),
+            foldbacks: rustc_hash::FxHashSet::default(),
+            repeats: None,
+            junctions: None,
+            stats: rustc_hash::FxHashMap::default(),
+            report_header: header::ReportHeader::read(&mut input, input_path)?,
+            empty: (Vec::new(), 0),
+        };
+
+        let jsonl = input
+            .fill_buf()
+            .with_context(|| error::Error::CantReadFile {
+                filename: input_path.to_string(),
+            })?
+            .first()
+            == Some(&b'{');
+        if jsonl {
+            report.read_jsonl(input, input_path)?;
+        } else {
+            report.read_yacrd(input, input_path)?;
+        }
+
+        if let Some(h) = &report.report_header {
+            if h.format > header::REPORT_VERSION {
+                log::warn!(
+                    "Report {} format version {} is newer than version {} supported by this yacrd",
+                    input_path,
+                    h.format,
+                    header::REPORT_VERSION
+                );
+            }
+        }
+
+        Ok(report)
+    }
+
+    fn read_yacrd(&mut self, input: Box<dyn std::io::BufRead>, input_path: &str) -> Result<()> {
+        let mut reader = csv::ReaderBuilder::new()
+            .delimiter(b'\t')
+            .has_headers(false)
+            .flexible(true)
+            .from_reader(input);
+
+        for (line, record) in reader.records().enumerate() {
+            let result = record.with_context(|| error::Error::Reading {
+                filename: input_path.to_string(),
+                format: util::FileType::Yacrd,
+            })?;
+
+            // trailing input line of streaming mode, other comment lines are ignored
+            if result[0].starts_with('#') {
+                if let Some(h) = self.report_header.as_mut() {
+                    let content = result.iter().collect::<Vec<&str>>().join("\t");
+                    h.read_line(&content, input_path)?;
+                }
+                continue;
+            }
+
+            let corrupt = || error::Error::CorruptYacrdReport {
+                name: input_path.to_string(),
+                line,
+            };
+
+            let len = util::str2usize(&result[2])?;
+            let (bad_part, bad_junctions) =
+                FromReport::parse_bad_string(&result[3]).with_context(corrupt)?;
+            let repeat_part = match result.get(4) {
+                Some(repeat_string) => Some(
+                    FromReport::parse_bad_string(repeat_string)
+                        .with_context(corrupt)?
+                        .0,
+                ),
+                None => None,
+            };
+
+            self.add_read(
+                &result[0],
+                result[1].to_string(),
+                len,
+                bad_part,
+                bad_junctions,
+                repeat_part,
+            );
+        }
+
+        Ok(())
+    }
+
+    fn read_jsonl(&mut self, input: Box<dyn std::io::BufRead>, input_path: &str) -> Result<()> {
+        for (line, content) in std::io::BufRead::lines(input).enumerate() {
+            let content = content.with_context(|| error::Error::Reading {
+                filename: input_path.to_string(),
+                format: util::FileType::Yacrd,
+            })?;
+            if content.trim().is_empty() {
+                continue;
+            }
+
+            let record = serde_json::from_str::<JsonLine>(&content).with_context(|| {
+                error::Error::CorruptYacrdReport {
+                    name: input_path.to_string(),
+                    line,
+                }
+            })?;
+
+            match record {
+                JsonLine::Header { yacrd_header } => self.report_header = Some(yacrd_header),
+                JsonLine::Input { yacrd_input } => {
+                    if let Some(h) = self.report_header.as_mut() {
+                        h.add_input(yacrd_input);
+                    }
+                }
+                JsonLine::Read(read) => {
+                    let (bad_part, bad_junctions) = read
+                        .bad_regions
+                        .iter()
+                        .map(|r| {
+                            (
+                                (r.begin, r.end),
+                                Junction {
+                                    score: r.score,
+                                    begin_ci: r.begin_ci,
+                                    end_ci: r.end_ci,
+                                },
+                            )
+                        })
+                        .unzip();
+                    let repeat_part = read
+                        .repeats
+                        .map(|repeats| repeats.iter().map(|r| (r.begin, r.end)).collect());
+                    if let Some(stats) = read.coverage {
+                        self.stats.insert(read.name.clone(), stats);
+                    }
+
+                    self.add_read(
+                        &read.read_type,
+                        read.name,
+                        read.length,
+                        bad_part,
+                        bad_junctions,
+                        repeat_part,
+                    );
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    fn add_read(
+        &mut self,
+        read_type: &str,
+        id: String,
+        len: usize,
+        bad_part: Vec<(u32, u32)>,
+        bad_junctions: Vec<Junction>,
+        repeat_part: Option<Vec<(u32, u32)>>,
+    ) {
+        if read_type == editor::ReadType::Foldback.as_str() {
+            self.foldbacks.insert(id.clone());
+        }
+
+        if bad_junctions.iter().any(|j| *j != Junction::default()) {
+            self.junctions
+                .get_or_insert_with(rustc_hash::FxHashMap::default)
+                .insert(id.clone(), bad_junctions);
+        }
+
+        if let Some(repeat_part) = repeat_part {
+            self.repeats
+                .get_or_insert_with(rustc_hash::FxHashMap::default)
+                .insert(id.clone(), repeat_part);
+        }
+
+        self.buffer.insert(id, (bad_part, len));
+    }
+
+    /// Get provenance header of report, None for legacy report without header
+    pub fn report_header(&self) -> Option<&header::ReportHeader> {
+        self.report_header.as_ref()
+    }
+
+    /// Warn for each parameter with a value different from the one in report header
+    pub fn check_params(&self, params: &[(String, String)], input_path: &str) {
+        if let Some(h) = &self.report_header {
+            for (name, old, new) in h.mismatch(params) {
+                log::warn!(
+                    "Report {} was produced with {} {}, it's now used with {}",
+                    input_path,
+                    name,
+                    old,
+                    new
+                );
+            }
+        }
+    }
+
+    fn parse_bad_string(bad_string: &str) -> Result<AnnotatedRegions> {
+        let mut ret = Vec::new();
+        let mut junctions = Vec::new();
+
+        if bad_string.is_empty() {
+            return Ok((ret, junctions));
+        }
+
+        for sub in bad_string.split(';') {
+            let mut iter = sub.split(',');
+            iter.next();
+
+            ret.push((
+                util::str2u32(
+                    iter.next()
+                        .with_context(|| error::Error::CorruptYacrdReportInPosition)?,
+                )?,
+                util::str2u32(
+                    iter.next()
+                        .with_context(|| error::Error::CorruptYacrdReportInPosition)?,
+                )?,
+            ));
+
+            /* score and confidence intervals of bounds (begin_low:begin_high:end_low:end_high) */
+            let mut junction = Junction::default();
+            for field in iter {
+                if field.contains(':') {
+                    let bounds = field
+                        .split(':')
+                        .map(util::str2u32)
+                        .collect::<Result<Vec<u32>>>()?;
+                    if bounds.len() != 4 {
+                        bail!(error::Error::CorruptYacrdReportInPosition);
+                    }
+
+                    junction.begin_ci = Some((bounds[0], bounds[1]));
+                    junction.end_ci = Some((bounds[2], bounds[3]));
+                } else {
+                    junction.score = Some(
+                        field
+                            .parse::<f64>()
+                            .with_context(|| error::Error::CorruptYacrdReportInPosition)?,
+                    );
+                }
+            }
+            junctions.push(junction);
+        }
+
+        Ok((ret, junctions))
+    }
+}
+
+impl BadPart for FromReport {
+    fn compute_all_bad_part(&mut self) {}
+
+    fn get_bad_part(&mut self, id: &str) -> Result<&(Vec<(u32, u32)>, usize)> {
+        match self.buffer.get(id) {
+            Some(v) => Ok(v),
+            None => Ok(&self.empty),
+        }
+    }
+
+    fn get_reads(&self) -> rustc_hash::FxHashSet<String> {
+        self.buffer.keys().map(|x| x.to_string()).collect()
+    }
+
+    fn is_foldback(&self, id: &str) -> bool {
+        self.foldbacks.contains(id)
+    }
+
+    fn get_repeat_part(&self, id: &str) -> Option<&[(u32, u32)]> {
+        self.repeats
+            .as_ref()
+            .map(|repeats| repeats.get(id).map(Vec::as_slice).unwrap_or(&[]))
+    }
+
+    fn get_junctions(&self, id: &str) -> Option<&[Junction]> {
+        self.junctions
+            .as_ref()
+            .map(|junctions| junctions.get(id).map(Vec::as_slice).unwrap_or(&[]))
+    }
+
+    fn get_read_stats(&self, id: &str) -> Option<ReadStats> {
+        self.stats.get(id).cloned()
+    }
+
+    fn header(&self) -> Vec<String> {
+        self.report_header
+            .as_ref()
+            .map(|h| h.comments.clone())
+            .unwrap_or_default()
+    }
+}
+
+/// Hide bad regions with a junction score lower than a cutoff, bad regions without score are kept.
+/// Junctions are filtered like bad regions, they're available for the read of the last call of
+/// [BadPart::get_bad_part].
+pub struct ScoreFilter<'a> {
+    inner: &'a mut dyn BadPart,
+    min_score: f64,
+    current_id: String,
+    current: (Vec<(u32, u32)>, usize),
+    current_junctions: Option<Vec<Junction>>,
+}
+
+impl<'a> ScoreFilter<'a> {
+    /// Create a new object, bad regions of `inner` with a score lower than `min_score` are hidden
+    pub fn new(inner: &'a mut dyn BadPart, min_score: f64) -> Self {
+        ScoreFilter {
+            inner,
+            min_score,
+            current_id: String::new(),
+            current: (Vec::new(), 0),
+            current_junctions: None,
+        }
+    }
+}
+
+impl BadPart for ScoreFilter<'_> {
+    fn compute_all_bad_part(&mut self) {
+        self.inner.compute_all_bad_part()
+    }
+
+    fn get_bad_part(&mut self, id: &str) -> Result<&(Vec<(u32, u32)>, usize)> {
+        let junctions = self.inner.get_junctions(id).map(<[_]>::to_vec);
+        let (bads, len) = self.inner.get_bad_part(id)?;
+
+        let keep: Vec<bool> = (0..bads.len())
+            .map(|i| {
+                match junctions
+                    .as_ref()
+                    .and_then(|j| j.get(i))
+                    .and_then(|j| j.score)
+                {
+                    Some(score) => score >= self.min_score,
+                    None => true,
+                }
+            })
+            .collect();
+
+        self.current_id = id.to_string();
+        self.current = (
+            bads.iter()
+                .zip(keep.iter())
+                .filter(|(_, keep)| **keep)
+                .map(|(bad, _)| *bad)
+                .collect(),
+            *len,
+        );
+        self.current_junctions = junctions.map(|junctions| {
+            junctions
+                .into_iter()
+                .zip(keep.iter())
+                .filter(|(_, keep)| **keep)
+                .map(|(junction, _)| junction)
+                .collect()
+        });
+
+        Ok(&self.current)
+    }
+
+    fn get_reads(&self) -> rustc_hash::FxHashSet<String> {
+        self.inner.get_reads()
+    }
+
+    fn is_foldback(&self, id: &str) -> bool {
+        self.inner.is_foldback(id)
+    }
+
+    fn get_repeat_part(&self, id: &str) -> Option<&[(u32, u32)]> {
+        self.inner.get_repeat_part(id)
+    }
+
+    fn get_junctions(&self, id: &str) -> Option<&[Junction]> {
+        if id == self.current_id {
+            self.current_junctions.as_deref()
+        } else {
+            None
+        }
+    }
+
+    fn get_read_stats(&self, id: &str) -> Option<ReadStats> {
+        self.inner.get_read_stats(id)
+    }
+
+    fn header(&self) -> Vec<String> {
+        self.inner.header()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::io::Write;
+
+    extern crate tempfile;
+    use self::tempfile::NamedTempFile;
+
+    use reads2ovl::Reads2Ovl;
+
+    #[test]
+    fn from_report() {
+        let mut report = NamedTempFile::new().expect("Can't create tmpfile");
+
+        writeln!(
+            report.as_file_mut(),
+            "NotBad	SRR8494940.65223	2706	1131,0,1131;16,2690,2706
+NotCovered	SRR8494940.141626	30116	326,0,326;27159,2957,30116
+Chimeric	SRR8494940.91655	15691	151,0,151;4056,7213,11269;58,15633,15691"
+        )
+        .expect("Error durring write of report in temp file");
+
+        let mut stack = FromReport::new(report.into_temp_path().to_str().unwrap())
+            .expect("Error when create stack object");
+
+        assert_eq!(
+            [
+                "SRR8494940.141626".to_string(),
+                "SRR8494940.65223".to_string(),
+                "SRR8494940.91655".to_string()
+            ]
+            .iter()
+            .cloned()
+            .collect::<rustc_hash::FxHashSet<String>>(),
+            stack.get_reads()
+        );
+
+        assert_eq!(
+            &(vec![(0, 1131), (2690, 2706)], 2706),
+            stack.get_bad_part("SRR8494940.65223").unwrap()
+        );
+        assert_eq!(
+            &(vec![(0, 326), (2957, 30116)], 30116),
+            stack.get_bad_part("SRR8494940.141626").unwrap()
+        );
+        assert_eq!(
+            &(vec![(0, 151), (7213, 11269), (15633, 15691)], 15691),
+            stack.get_bad_part("SRR8494940.91655").unwrap()
+        );
+    }
+
+    #[test]
+    fn from_overlap() {
+        let mut ovl = reads2ovl::FullMemory::new(8192);
+
+        ovl.add_overlap("A".to_string(), (10, 990)).unwrap();
+        ovl.add_length("A".to_string(), 1000);
+
+        ovl.add_overlap("B".to_string(), (10, 90)).unwrap();
+        ovl.add_length("B".to_string(), 1000);
+
+        ovl.add_overlap("C".to_string(), (10, 490)).unwrap();
+        ovl.add_overlap("C".to_string(), (510, 990)).unwrap();
+        ovl.add_length("C".to_string(), 1000);
+
+        ovl.add_overlap("D".to_string(), (0, 990)).unwrap();
+        ovl.add_length("D".to_string(), 1000);
+
+        ovl.add_overlap("E".to_string(), (10, 1000)).unwrap();
+        ovl.add_length("E".to_string(), 1000);
+
+        ovl.add_overlap("F".to_string(), (0, 490)).unwrap();
+        ovl.add_overlap("F".to_string(), (510, 1000)).unwrap();
+        ovl.add_length("F".to_string(), 1000);
+
+        let mut stack = FromOverlap::new(Box::new(ovl), 0);
+
+        stack.compute_all_bad_part();
+
+        assert_eq!(
+            [
+                "A".to_string(),
+                "B".to_string(),
+                "C".to_string(),
+                "D".to_string(),
+                "E".to_string(),
+                "F".to_string()
+            ]
+            .iter()
+            .cloned()
+            .collect::<rustc_hash::FxHashSet<String>>(),
+            stack.get_reads()
+        );
+
+        assert_eq!(
+            &(vec![(0, 10), (990, 1000)], 1000),
+            stack.get_bad_part("A").unwrap()
+        );
+        assert_eq!(
+            &(vec![(0, 10), (90, 1000)], 1000),
+            stack.get_bad_part("B").unwrap()
+        );
+        assert_eq!(
+            &(vec![(0, 10), (490, 510), (990, 1000)], 1000),
+            stack.get_bad_part("C").unwrap()
+        );
+        assert_eq!(&(vec![(990, 1000)], 1000), stack.get_bad_part("D").unwrap());
+        assert_eq!(&(vec![(0, 10)], 1000), stack.get_bad_part("E").unwrap());
+        assert_eq!(&(vec![(490, 510)], 1000), stack.get_bad_part("F").unwrap());
+    }
+
+    #[test]
+    fn coverage_upper_than_0() {
+        let mut ovl = reads2ovl::FullMemory::new(8192);
+
+        ovl.add_length("A".to_string(), 1000);
+
+        ovl.add_overlap("A".to_string(), (0, 425)).unwrap();
+        ovl.add_overlap("A".to_string(), (0, 450)).unwrap();
+        ovl.add_overlap("A".to_string(), (0, 475)).unwrap();
+
+        ovl.add_overlap("A".to_string(), (525, 1000)).unwrap();
+        ovl.add_overlap("A".to_string(), (550, 1000)).unwrap();
+        ovl.add_overlap("A".to_string(), (575, 1000)).unwrap();
+
+        let mut stack = FromOverlap::new(Box::new(ovl), 2);
+
+        stack.compute_all_bad_part();
+
+        assert_eq!(&(vec![(425, 575)], 1000), stack.get_bad_part("A").unwrap());
+    }
+
+    /* bad region detection before coverage profile, kept to check that results didn't change */
+    fn stack_scan(mut ovls: Vec<(u32, u32)>, len: usize, coverage: usize) -> Vec<(u32, u32)> {
+        let mut gaps: Vec<(u32, u32)> = Vec::new();
+        let mut stack: std::collections::BinaryHeap<std::cmp::Reverse<u32>> =
+            std::collections::BinaryHeap::new();
+
+        ovls.sort_unstable();
+
+        let mut first_covered = 0;
+        let mut last_covered = 0;
+
+        for interval in ovls {
+            while let Some(head) = stack.peek() {
+                if head.0 > interval.0 {
+                    break;
+                }
+
+                if stack.len() > coverage {
+                    last_covered = head.0;
+                }
+                stack.pop();
+            }
+
+            if stack.len() <= coverage {
+                if last_covered != 0 {
+                    gaps.push((last_covered, interval.0));
+                } else {
+                    first_covered = interval.0;
+                }
+            }
+            stack.push(std::cmp::Reverse(interval.1));
+        }
+
+        while stack.len() > coverage {
+            last_covered = stack.peek().unwrap().0;
+            if last_covered as usize >= len {
+                break;
+            }
+            stack.pop();
+        }
+
+        if first_covered != 0 {
+            gaps.insert(0, (0, first_covered));
+        }
+
+        if last_covered as usize != len {
+            gaps.push((last_covered, len as u32));
+        }
+
+        let mut clean_gaps: Vec<(u32, u32)> = Vec::new();
+        for gap in gaps {
+            match clean_gaps.last_mut() {
+                Some(last) if last.0 == gap.0 => last.1 = last.1.max(gap.1),
+                _ => clean_gaps.push(gap),
+            }
+        }
+
+        /* stack scan reports empty regions where an overlap ends at the begin of the next one */
+        clean_gaps.retain(|(begin, end)| begin < end);
+
+        clean_gaps
+    }
+
+    #[test]
+    fn same_as_stack_scan() {
+        let mut seed: u64 = 42;
+        let mut next = |max: u32| {
+            seed = seed
+                .wrapping_mul(6364136223846793005)
+                .wrapping_add(1442695040888963407);
+            ((seed >> 33) % max as u64) as u32
+        };
+
+        for _ in 0..500 {
+            let len = 1000;
+            let ovls: Vec<(u32, u32)> = (0..next(12) + 1)
+                .map(|_| {
+                    let begin = next(900);
+                    (begin, (begin + 50 + next(600)).min(len))
+                })
+                .collect();
+
+            for coverage in 0..4 {
+                assert_eq!(
+                    stack_scan(ovls.clone(), len as usize, coverage as usize),
+                    FromOverlap::compute_bad_part(
+                        ovls.clone(),
+                        len as usize,
+                        &DetectionParams::new(coverage)
+                    ),
+                    "{:?} coverage {}",
+                    ovls,
+                    coverage
+                );
+            }
+        }
+    }
+
+    #[test]
+    #[allow(clippy::assertions_on_constants, clippy::nonminimal_bool)]
+    fn failled_correctly_on_corrupt_yacrd() {
+        let mut report = NamedTempFile::new().expect("Can't create tmpfile");
+
+        writeln!(
+            report.as_file_mut(),
             "NotBad	SRR8494940.65223	2706	1131,0,1131;16,2690,2706
 NotCovered	SRR8494940.141626	30116	326,0,326;27159,2957,30116
-Chimeric	SRR8494940.91655	15691	151,0,151;4056,7213,11269;58,15633,15691"
+Chimeric	SRR8494940.91655	15691	151,0,151;4056,7213,11269;58,156"
+        )
+        .unwrap();
+
+        let stack = FromReport::new(report.into_temp_path().to_str().unwrap());
+
+        if !stack.is_err() {
+            assert!(false);
+        }
+    }
+
+    #[test]
+    fn perfect_read_in_report() {
+        let mut report = NamedTempFile::new().expect("Can't create tmpfile");
+
+        writeln!(report.as_file_mut(), "NotBad	perfect	2706	")
+            .expect("Error durring write of report in temp file");
+
+        let mut stack = FromReport::new(report.into_temp_path().to_str().unwrap())
+            .expect("Error when create stack object");
+
+        assert_eq!(
+            ["perfect".to_string()]
+                .iter()
+                .cloned()
+                .collect::<rustc_hash::FxHashSet<String>>(),
+            stack.get_reads()
+        );
+
+        assert_eq!(&(vec![], 2706), stack.get_bad_part("perfect").unwrap());
+    }
+
+    fn foldback_pair(partner: &str, ovl_a: (u32, u32), reverse: bool) -> reads2ovl::OverlapPair {
+        reads2ovl::OverlapPair {
+            id_a: "R".to_string(),
+            ovl_a,
+            len_a: 2000,
+            id_b: partner.to_string(),
+            ovl_b: (0, 800),
+            len_b: 1000,
+            reverse,
+        }
+    }
+
+    #[test]
+    fn foldback() {
+        let mut ovl = reads2ovl::FullMemory::new(8192);
+        assert!(ovl.keep_details());
+
+        for partner in ["P1", "P2"] {
+            ovl.add_overlap_pair(foldback_pair(partner, (100, 900), false))
+                .unwrap();
+            ovl.add_overlap_pair(foldback_pair(partner, (1100, 1900), true))
+                .unwrap();
+        }
+
+        let mut params = DetectionParams::new(0);
+        params.foldback = true;
+        let mut stack = FromOverlap::with_params(Box::new(ovl), params);
+        stack.compute_all_bad_part();
+
+        assert_eq!(Some(1000), stack.foldback_position("R"));
+        assert_eq!(
+            &(vec![(0, 100), (900, 2000)], 2000),
+            stack.get_bad_part("R").unwrap()
+        );
+        assert_eq!(
+            editor::ReadType::Foldback,
+            stack.get_read_type("R", 0.8).unwrap()
+        );
+
+        assert_eq!(None, stack.foldback_position("P1"));
+        assert_eq!(
+            editor::ReadType::NotBad,
+            stack.get_read_type("P1", 0.8).unwrap()
+        );
+    }
+
+    #[test]
+    fn not_foldback() {
+        let mut ovl = reads2ovl::FullMemory::new(8192);
+        ovl.keep_details();
+
+        ovl.add_overlap_pair(foldback_pair("P1", (100, 900), false))
+            .unwrap();
+        ovl.add_overlap_pair(foldback_pair("P2", (1100, 1900), true))
+            .unwrap();
+        ovl.add_overlap_pair(foldback_pair("P3", (100, 1900), false))
+            .unwrap();
+
+        let mut params = DetectionParams::new(0);
+        params.foldback = true;
+        let mut stack = FromOverlap::with_params(Box::new(ovl), params);
+        stack.compute_all_bad_part();
+
+        assert_eq!(None, stack.foldback_position("R"));
+    }
+
+    #[test]
+    fn foldback_in_report() {
+        let report: &[u8] = b"Foldback\tR\t2000\t100,0,100;1100,900,2000
+NotBad\tP1\t1000\t200,800,1000
+";
+
+        let mut stack = FromReport::from_reader(Box::new(report), "report").unwrap();
+
+        assert!(stack.is_foldback("R"));
+        assert_eq!(
+            editor::ReadType::Foldback,
+            stack.get_read_type("R", 0.8).unwrap()
+        );
+        assert_eq!(
+            editor::ReadType::NotBad,
+            stack.get_read_type("P1", 0.8).unwrap()
+        );
+    }
+
+    #[test]
+    fn coverage_threshold() {
+        assert_eq!(Ok(CoverageThreshold::Auto), "auto".parse());
+        assert_eq!(Ok(CoverageThreshold::Value(3)), "3".parse());
+        assert!("three".parse::<CoverageThreshold>().is_err());
+    }
+
+    #[test]
+    fn histogram() {
+        let mut ovls = reads2ovl::MapReads2Ovl::default();
+        ovls.insert("A".to_string(), (vec![(0, 600), (400, 1000)], 1000));
+        ovls.insert("B".to_string(), (vec![(100, 900)], 1000));
+
+        let histogram = FromOverlap::coverage_histogram(&ovls);
+
+        assert_eq!(vec![200, 1600, 200], histogram);
+        assert_eq!(1, FromOverlap::modal(&histogram));
+        assert_eq!(0, FromOverlap::modal(&[1000]));
+    }
+
+    #[test]
+    fn auto_coverage() {
+        let mut ovl = reads2ovl::FullMemory::new(8192);
+
+        for read in ["A", "B"] {
+            ovl.add_length(read.to_string(), 1000);
+            for _ in 0..20 {
+                ovl.add_overlap(read.to_string(), (0, 1000)).unwrap();
+            }
+        }
+
+        ovl.add_length("C".to_string(), 1000);
+        for _ in 0..5 {
+            ovl.add_overlap("C".to_string(), (0, 400)).unwrap();
+            ovl.add_overlap("C".to_string(), (600, 1000)).unwrap();
+        }
+
+        let mut stack = FromOverlap::with_params(
+            Box::new(ovl),
+            DetectionParams {
+                auto_coverage: Some(0.2),
+                read_stats: true,
+                ..DetectionParams::new(0)
+            },
+        );
+
+        stack.compute_all_bad_part();
+
+        assert_eq!(Some(20), stack.modal_coverage());
+        assert_eq!(4, stack.coverage());
+        assert_eq!(&(vec![], 1000), stack.get_bad_part("A").unwrap());
+        assert_eq!(&(vec![(400, 600)], 1000), stack.get_bad_part("C").unwrap());
+        assert_eq!(
+            Some(ReadStats {
+                median: 5,
+                threshold: 4,
+                relative_threshold: None,
+                modal: Some(20),
+            }),
+            stack.get_read_stats("C")
+        );
+        assert_eq!(
+            vec!["coverage threshold: 4 (auto, 0.2 of modal coverage 20)".to_string()],
+            stack.header()
+        );
+    }
+
+    #[test]
+    fn report_with_header() {
+        let report: &[u8] = b"# coverage threshold: 4 (auto, 0.2 of modal coverage 20)
+NotBad\tP1\t1000\t200,800,1000
+";
+
+        let mut stack = FromReport::from_reader(Box::new(report), "report").unwrap();
+
+        assert_eq!(
+            &(vec![(800, 1000)], 1000),
+            stack.get_bad_part("P1").unwrap()
+        );
+    }
+
+    #[test]
+    fn report_with_provenance() {
+        let report: &[u8] = b"# format: yacrd-report 2
+# version: 1.0.0
+# param: not-coverage=0.8
+# input: reads.paf crc32:0000beef
+# coverage threshold: 4 (auto, 0.2 of modal coverage 20)
+NotBad\tP1\t1000\t200,800,1000
+";
+
+        let mut stack = FromReport::from_reader(Box::new(report), "report").unwrap();
+
+        let h = stack.report_header().unwrap();
+        assert_eq!(2, h.format);
+        assert_eq!(Some("0.8"), h.get_param("not-coverage"));
+        assert_eq!(Some(0xbeef), h.inputs[0].crc32);
+        assert_eq!(
+            vec!["coverage threshold: 4 (auto, 0.2 of modal coverage 20)".to_string()],
+            stack.header()
+        );
+        assert_eq!(
+            &(vec![(800, 1000)], 1000),
+            stack.get_bad_part("P1").unwrap()
+        );
+
+        let legacy: &[u8] = b"NotBad\tP1\t1000\t200,800,1000\n";
+        let stack = FromReport::from_reader(Box::new(legacy), "report").unwrap();
+        assert!(stack.report_header().is_none());
+        assert!(stack.header().is_empty());
+    }
+
+    #[test]
+    fn report_with_trailing_input() {
+        let report: &[u8] = b"# format: yacrd-report 2
+# version: 1.0.0
+# input: -
+NotBad\tP1\t1000\t200,800,1000
+# input: - crc32:0000beef
+";
+
+        let mut stack = FromReport::from_reader(Box::new(report), "report").unwrap();
+
+        let h = stack.report_header().unwrap();
+        assert_eq!(1, h.inputs.len());
+        assert_eq!(Some(0xbeef), h.inputs[0].crc32);
+        assert_eq!(
+            &(vec![(800, 1000)], 1000),
+            stack.get_bad_part("P1").unwrap()
+        );
+
+        let report: &[u8] = br#"{"yacrd_header":{"format":2,"version":"1.0.0","command":null,"params":[],"inputs":[{"path":"-","crc32":null}],"comments":[]}}
+{"type":"NotBad","name":"P1","length":1000,"bad_regions":[{"begin":800,"end":1000,"length":200}]}
+{"yacrd_input":{"path":"-","crc32":48879}}
+"#;
+
+        let stack = FromReport::from_reader(Box::new(report), "report").unwrap();
+        assert_eq!(Some(0xbeef), stack.report_header().unwrap().inputs[0].crc32);
+    }
+
+    #[test]
+    fn jsonl_report() {
+        let mut provenance = header::ReportHeader::new();
+        provenance.add_param("not-coverage", 0.8);
+
+        let mut report = Vec::new();
+        provenance.write_jsonl(&mut report).unwrap();
+        editor::report_jsonl(
+            "A",
+            2000,
+            &[(0, 10), (1000, 1100)],
+            &editor::ReadAnnotations {
+                junctions: Some(&[
+                    Junction::default(),
+                    Junction {
+                        score: Some(0.9),
+                        begin_ci: Some((990, 1010)),
+                        end_ci: Some((1090, 1120)),
+                    },
+                ]),
+                repeats: Some(&[(1500, 1800)]),
+                stats: Some(ReadStats {
+                    median: 20,
+                    threshold: 4,
+                    relative_threshold: Some(10.0),
+                    modal: None,
+                }),
+            },
+            &editor::ReadType::Chimeric,
+            &mut report,
         )
-        .expect("Error durring write of report in temp file");
+        .unwrap();
+        editor::report_jsonl(
+            "B",
+            1000,
+            &[(500, 1000)],
+            &editor::ReadAnnotations {
+                repeats: Some(&[]),
+                ..Default::default()
+            },
+            &editor::ReadType::Foldback,
+            &mut report,
+        )
+        .unwrap();
 
-        let mut stack = FromReport::new(report.into_temp_path().to_str().unwrap())
-            .expect("Error when create stack object");
+        let mut stack =
+            FromReport::from_reader(Box::new(std::io::Cursor::new(report)), "report").unwrap();
 
+        assert_eq!(Some(&provenance), stack.report_header());
         assert_eq!(
-            [
-                "SRR8494940.65223".to_string(),
-                "SRR8494940.141626".to_string(),
-                "SRR8494940.91655".to_string()
-            ]
-            .iter()
-            .cloned()
-            .collect::<rustc_hash::FxHashSet<String>>(),
-            stack.get_reads()
+            &(vec![(0, 10), (1000, 1100)], 2000),
+            stack.get_bad_part("A").unwrap()
+        );
+        assert_eq!(
+            Some(
+                &[
+                    Junction::default(),
+                    Junction {
+                        score: Some(0.9),
+                        begin_ci: Some((990, 1010)),
+                        end_ci: Some((1090, 1120)),
+                    }
+                ][..]
+            ),
+            stack.get_junctions("A")
         );
+        assert_eq!(Some(&[(1500, 1800)][..]), stack.get_repeat_part("A"));
+        assert_eq!(
+            Some(ReadStats {
+                median: 20,
+                threshold: 4,
+                relative_threshold: Some(10.0),
+                modal: None,
+            }),
+            stack.get_read_stats("A")
+        );
+        assert!(stack.is_foldback("B"));
+        assert_eq!(Some(&[][..]), stack.get_repeat_part("B"));
+        assert_eq!(None, stack.get_read_stats("B"));
+
+        let corrupt: &[u8] = b"{\"type\":\"NotBad\",\"name\":\"A\"}\n";
+        assert!(FromReport::from_reader(Box::new(corrupt), "report").is_err());
+    }
 
+    #[test]
+    fn median_coverage() {
+        assert_eq!(0, FromOverlap::median_coverage(&[]));
         assert_eq!(
-            &(vec![(0, 1131), (2690, 2706)], 2706),
-            stack.get_bad_part("SRR8494940.65223").unwrap()
+            2,
+            FromOverlap::median_coverage(&[(0, 100, 0), (100, 700, 2), (700, 1000, 30)])
         );
         assert_eq!(
-            &(vec![(0, 326), (2957, 30116)], 30116),
-            stack.get_bad_part("SRR8494940.141626").unwrap()
+            30,
+            FromOverlap::median_coverage(&[(0, 100, 0), (100, 300, 2), (300, 1000, 30)])
+        );
+    }
+
+    #[test]
+    fn relative_coverage() {
+        let mut ovls = Vec::new();
+        for _ in 0..20 {
+            ovls.push((0, 450));
+            ovls.push((550, 1000));
+        }
+        ovls.push((0, 1000));
+
+        /* coverage 1 in middle is kept with absolute threshold */
+        assert_eq!(
+            Vec::<(u32, u32)>::new(),
+            FromOverlap::compute_bad_part(ovls.clone(), 1000, &DetectionParams::new(0))
         );
+
+        let params = DetectionParams {
+            relative_coverage: Some(0.1),
+            ..DetectionParams::new(0)
+        };
         assert_eq!(
-            &(vec![(0, 151), (7213, 11269), (15633, 15691)], 15691),
-            stack.get_bad_part("SRR8494940.91655").unwrap()
+            vec![(450, 550)],
+            FromOverlap::compute_bad_part(ovls.clone(), 1000, &params)
+        );
+
+        /* low coverage read isn't bad */
+        let low = vec![(0, 1000), (400, 600)];
+        assert_eq!(
+            Vec::<(u32, u32)>::new(),
+            FromOverlap::compute_bad_part(low.clone(), 1000, &params)
+        );
+
+        /* absolute minimum is still used */
+        let params = DetectionParams {
+            relative_coverage: Some(0.1),
+            ..DetectionParams::new(1)
+        };
+        assert_eq!(
+            vec![(0, 400), (600, 1000)],
+            FromOverlap::compute_bad_part(low, 1000, &params)
         );
     }
 
     #[test]
-    fn from_overlap() {
+    fn hysteresis() {
+        /* coverage: 3 on [0, 100), 1 on [100, 200), 0 on [200, 300), 1 on [300, 400), 3 on [400, 1000) */
+        let ovls = vec![
+            (0, 200),
+            (0, 100),
+            (0, 100),
+            (300, 1000),
+            (400, 1000),
+            (400, 1000),
+        ];
+
+        assert_eq!(
+            vec![(200, 300)],
+            FromOverlap::compute_bad_part(ovls.clone(), 1000, &DetectionParams::new(0))
+        );
+
+        let params = DetectionParams {
+            leave_coverage: Some(1),
+            ..DetectionParams::new(0)
+        };
+        assert_eq!(
+            vec![(100, 400)],
+            FromOverlap::compute_bad_part(ovls.clone(), 1000, &params)
+        );
+
+        /* a region under leave threshold without bad coverage isn't bad */
+        let params = DetectionParams {
+            leave_coverage: Some(2),
+            ..DetectionParams::new(0)
+        };
+        assert_eq!(
+            Vec::<(u32, u32)>::new(),
+            FromOverlap::compute_bad_part(vec![(0, 1000), (0, 1000), (0, 100)], 1000, &params)
+        );
+    }
+
+    #[test]
+    fn merge_and_min_length() {
+        let ovls = vec![(0, 100), (110, 500), (510, 520), (600, 1000)];
+
+        assert_eq!(
+            vec![(100, 110), (500, 510), (520, 600)],
+            FromOverlap::compute_bad_part(ovls.clone(), 1000, &DetectionParams::new(0))
+        );
+
+        let params = DetectionParams {
+            min_length: 20,
+            ..DetectionParams::new(0)
+        };
+        assert_eq!(
+            vec![(520, 600)],
+            FromOverlap::compute_bad_part(ovls.clone(), 1000, &params)
+        );
+
+        let params = DetectionParams {
+            merge_distance: 10,
+            min_length: 20,
+            ..DetectionParams::new(0)
+        };
+        assert_eq!(
+            vec![(500, 600)],
+            FromOverlap::compute_bad_part(ovls, 1000, &params)
+        );
+    }
+
+    #[test]
+    fn repeat() {
         let mut ovl = reads2ovl::FullMemory::new(8192);
 
-        ovl.add_overlap("A".to_string(), (10, 990)).unwrap();
         ovl.add_length("A".to_string(), 1000);
+        ovl.add_overlap("A".to_string(), (0, 1000)).unwrap();
+        ovl.add_overlap("A".to_string(), (0, 1000)).unwrap();
+        for _ in 0..4 {
+            ovl.add_overlap("A".to_string(), (200, 300)).unwrap();
+        }
 
-        ovl.add_overlap("B".to_string(), (10, 90)).unwrap();
         ovl.add_length("B".to_string(), 1000);
+        ovl.add_overlap("B".to_string(), (0, 1000)).unwrap();
+
+        let mut stack = FromOverlap::with_params(
+            Box::new(ovl),
+            DetectionParams {
+                repeat_coverage: Some(3.0),
+                ..DetectionParams::new(0)
+            },
+        );
 
-        ovl.add_overlap("C".to_string(), (10, 490)).unwrap();
-        ovl.add_overlap("C".to_string(), (510, 990)).unwrap();
-        ovl.add_length("C".to_string(), 1000);
+        stack.compute_all_bad_part();
 
-        ovl.add_overlap("D".to_string(), (0, 990)).unwrap();
-        ovl.add_length("D".to_string(), 1000);
+        assert_eq!(Some(&[(200, 300)][..]), stack.get_repeat_part("A"));
+        assert_eq!(Some(&[][..]), stack.get_repeat_part("B"));
+        assert_eq!(
+            editor::ReadType::Repeat,
+            stack.get_read_type("A", 0.8).unwrap()
+        );
+        assert_eq!(
+            editor::ReadType::NotBad,
+            stack.get_read_type("B", 0.8).unwrap()
+        );
+    }
 
-        ovl.add_overlap("E".to_string(), (10, 1000)).unwrap();
-        ovl.add_length("E".to_string(), 1000);
+    #[test]
+    fn coverage_profile_written() {
+        let mut ovl = reads2ovl::FullMemory::new(8192);
 
-        ovl.add_overlap("F".to_string(), (0, 490)).unwrap();
-        ovl.add_overlap("F".to_string(), (510, 1000)).unwrap();
-        ovl.add_length("F".to_string(), 1000);
+        ovl.add_length("B".to_string(), 500);
+        ovl.add_overlap("B".to_string(), (0, 500)).unwrap();
+        ovl.add_length("A".to_string(), 1000);
+        ovl.add_overlap("A".to_string(), (0, 600)).unwrap();
+        ovl.add_overlap("A".to_string(), (400, 900)).unwrap();
 
-        let mut stack = FromOverlap::new(Box::new(ovl), 0);
+        let bedgraph = NamedTempFile::new().unwrap();
 
+        let mut stack = FromOverlap::new(Box::new(ovl), 0);
+        stack.write_coverage_profile(Box::new(bedgraph.reopen().unwrap()));
         stack.compute_all_bad_part();
+        stack.finish().unwrap();
 
         assert_eq!(
-            [
-                "A".to_string(),
-                "B".to_string(),
-                "C".to_string(),
-                "D".to_string(),
-                "E".to_string(),
-                "F".to_string()
-            ]
-            .iter()
-            .cloned()
-            .collect::<rustc_hash::FxHashSet<String>>(),
-            stack.get_reads()
+            "A\t0\t400\t1\nA\t400\t600\t2\nA\t600\t900\t1\nA\t900\t1000\t0\nB\t0\t500\t1\n",
+            std::fs::read_to_string(bedgraph.path()).unwrap()
         );
 
+        let parts = FromOverlap::compute_parts(vec![(0, 600)], 1000, &DetectionParams::new(0));
+        assert!(parts.profile.is_empty());
+    }
+
+    #[test]
+    fn repeat_in_report() {
+        let report: &[u8] = b"Repeat\tR\t1000\t\t100,200,300
+NotBad\tP1\t1000\t200,800,1000\t
+";
+
+        let mut stack = FromReport::from_reader(Box::new(report), "report").unwrap();
+
+        assert_eq!(Some(&[(200, 300)][..]), stack.get_repeat_part("R"));
+        assert_eq!(Some(&[][..]), stack.get_repeat_part("P1"));
         assert_eq!(
-            &(vec![(0, 10), (990, 1000)], 1000),
-            stack.get_bad_part("A").unwrap()
+            editor::ReadType::Repeat,
+            stack.get_read_type("R", 0.8).unwrap()
         );
+
+        let report: &[u8] = b"NotBad\tP1\t1000\t200,800,1000\n";
+        let stack = FromReport::from_reader(Box::new(report), "report").unwrap();
+        assert_eq!(None, stack.get_repeat_part("P1"));
+    }
+
+    fn junction_details() -> Vec<reads2ovl::OverlapDetail> {
+        let mut details = Vec::new();
+        for i in 0..5 {
+            details.push(reads2ovl::OverlapDetail {
+                interval: (0, 2100),
+                reverse: false,
+                partner: reads2ovl::name_hash(&format!("L{}", i)),
+            });
+            details.push(reads2ovl::OverlapDetail {
+                interval: (1900, 4000),
+                reverse: false,
+                partner: reads2ovl::name_hash(&format!("R{}", i)),
+            });
+        }
+
+        details
+    }
+
+    #[test]
+    fn partner_junction() {
+        let mut details = junction_details();
+
         assert_eq!(
-            &(vec![(0, 10), (90, 1000)], 1000),
-            stack.get_bad_part("B").unwrap()
+            vec![(1900, 2100)],
+            FromOverlap::compute_partner_junctions(&details, 4000)
+        );
+
+        /* partners spanning position */
+        for i in 0..5 {
+            details.push(reads2ovl::OverlapDetail {
+                interval: (500, 3500),
+                reverse: false,
+                partner: reads2ovl::name_hash(&format!("S{}", i)),
+            });
+        }
+
+        assert!(FromOverlap::compute_partner_junctions(&details, 4000).is_empty());
+    }
+
+    /* partners of each side counted by checking all overlaps at each position */
+    fn partner_sides(details: &[reads2ovl::OverlapDetail], pos: u32) -> (usize, usize, usize) {
+        let mut left = rustc_hash::FxHashSet::default();
+        let mut right = rustc_hash::FxHashSet::default();
+        let mut spanning = rustc_hash::FxHashSet::default();
+        let (window_begin, window_end) = (pos - PARTNER_FLANK, pos + PARTNER_FLANK);
+        for detail in details {
+            let (begin, end) = detail.interval;
+            if begin <= window_begin && end >= window_end {
+                spanning.insert(detail.partner);
+            } else if begin < window_begin && end > window_begin {
+                left.insert(detail.partner);
+            } else if begin < window_end && end > window_end {
+                right.insert(detail.partner);
+            }
+        }
+
+        (left.len(), right.len(), spanning.len())
+    }
+
+    #[test]
+    fn partner_junction_sweep() {
+        let mut seed: u64 = 7;
+        let mut next = |max: u32| {
+            seed = seed
+                .wrapping_mul(6364136223846793005)
+                .wrapping_add(1442695040888963407);
+            ((seed >> 33) % max as u64) as u32
+        };
+
+        for _ in 0..200 {
+            let len = 5000;
+            let details: Vec<reads2ovl::OverlapDetail> = (0..next(30) + 1)
+                .map(|_| {
+                    let begin = next(4500);
+                    reads2ovl::OverlapDetail {
+                        interval: (begin, (begin + 100 + next(3000)).min(len)),
+                        reverse: false,
+                        partner: next(10) as u64,
+                    }
+                })
+                .collect();
+
+            let mut junctions: Vec<(u32, u32)> = Vec::new();
+            let mut candidates = details
+                .iter()
+                .flat_map(|d| [d.interval.0, d.interval.1])
+                .filter(|pos| *pos >= PARTNER_FLANK && *pos + PARTNER_FLANK <= len)
+                .collect::<Vec<u32>>();
+            candidates.sort_unstable();
+            candidates.dedup();
+            for pos in candidates {
+                let (left, right, spanning) = partner_sides(&details, pos);
+                let support = left.min(right);
+                if support < PARTNER_MIN_SUPPORT
+                    || spanning as f64 > support as f64 * PARTNER_MAX_SPANNING
+                {
+                    continue;
+                }
+                match junctions.last_mut() {
+                    Some(last) if pos - last.1 <= PARTNER_FLANK => last.1 = pos,
+                    _ => junctions.push((pos, pos)),
+                }
+            }
+            for junction in junctions.iter_mut() {
+                junction.1 = junction.1.max(junction.0 + 1);
+            }
+
+            assert_eq!(
+                junctions,
+                FromOverlap::compute_partner_junctions(&details, len as usize)
+            );
+        }
+    }
+
+    #[test]
+    fn partner_junction_in_bad_part() {
+        let mut ovl = reads2ovl::FullMemory::new(8192);
+        ovl.keep_details();
+
+        for i in 0..5 {
+            ovl.add_overlap_pair(reads2ovl::OverlapPair {
+                id_a: "A".to_string(),
+                ovl_a: (0, 2100),
+                len_a: 4000,
+                id_b: format!("L{}", i),
+                ovl_b: (1900, 4000),
+                len_b: 4000,
+                reverse: false,
+            })
+            .unwrap();
+            ovl.add_overlap_pair(reads2ovl::OverlapPair {
+                id_a: "A".to_string(),
+                ovl_a: (1900, 4000),
+                len_a: 4000,
+                id_b: format!("R{}", i),
+                ovl_b: (0, 2100),
+                len_b: 4000,
+                reverse: false,
+            })
+            .unwrap();
+        }
+
+        let mut stack = FromOverlap::with_params(
+            Box::new(ovl),
+            DetectionParams {
+                partner_junction: true,
+                ..DetectionParams::new(0)
+            },
         );
+
+        stack.compute_all_bad_part();
+
         assert_eq!(
-            &(vec![(0, 10), (490, 510), (990, 1000)], 1000),
-            stack.get_bad_part("C").unwrap()
+            &(vec![(1900, 2100)], 4000),
+            stack.get_bad_part("A").unwrap()
+        );
+        assert_eq!(
+            editor::ReadType::Chimeric,
+            stack.get_read_type("A", 0.8).unwrap()
         );
-        assert_eq!(&(vec![(990, 1000)], 1000), stack.get_bad_part("D").unwrap());
-        assert_eq!(&(vec![(0, 10)], 1000), stack.get_bad_part("E").unwrap());
-        assert_eq!(&(vec![(490, 510)], 1000), stack.get_bad_part("F").unwrap());
     }
 
     #[test]
-    fn coverage_upper_than_0() {
-        let mut ovl = reads2ovl::FullMemory::new(8192);
+    fn junction_score() {
+        let mut ovls = Vec::new();
+        for _ in 0..10 {
+            ovls.push((0, 1000));
+            ovls.push((1100, 2000));
+        }
+        let profile = FromOverlap::coverage_profile(&ovls, 2000);
 
-        ovl.add_length("A".to_string(), 1000);
+        let scores = FromOverlap::junction_scores(&ovls, &profile, &[(1000, 1100)], 2000);
+        let score = scores[0].unwrap();
+        assert!(score > 0.7, "{}", score);
 
-        ovl.add_overlap("A".to_string(), (0, 425)).unwrap();
-        ovl.add_overlap("A".to_string(), (0, 450)).unwrap();
-        ovl.add_overlap("A".to_string(), (0, 475)).unwrap();
+        /* coverage doesn't drop and overlaps don't end around region */
+        let mut spanning = ovls.clone();
+        for _ in 0..10 {
+            spanning.push((0, 2000));
+        }
+        let profile = FromOverlap::coverage_profile(&spanning, 2000);
+        let low = FromOverlap::junction_score(&spanning, &profile, (1000, 1100));
+        assert!(low < score, "{} {}", low, score);
 
-        ovl.add_overlap("A".to_string(), (525, 1000)).unwrap();
-        ovl.add_overlap("A".to_string(), (550, 1000)).unwrap();
-        ovl.add_overlap("A".to_string(), (575, 1000)).unwrap();
+        assert_eq!(
+            vec![None, Some(0.0)],
+            FromOverlap::junction_scores(&[], &[(0, 2000, 0)], &[(0, 10), (100, 200)], 2000)
+        );
+    }
 
-        let mut stack = FromOverlap::new(Box::new(ovl), 2);
+    #[test]
+    fn score_filter() {
+        let report: &[u8] = b"Chimeric\tA\t2000\t10,0,10;100,1000,1100,0.900
+Chimeric\tB\t2000\t100,1000,1100,0.200;10,1990,2000
+Chimeric\tC\t2000\t100,1000,1100
+";
 
-        stack.compute_all_bad_part();
+        let mut stack = FromReport::from_reader(Box::new(report), "report").unwrap();
+        assert_eq!(
+            vec![Some(0.2), None],
+            stack
+                .get_junctions("B")
+                .unwrap()
+                .iter()
+                .map(|j| j.score)
+                .collect::<Vec<Option<f64>>>()
+        );
 
-        assert_eq!(&(vec![(425, 575)], 1000), stack.get_bad_part("A").unwrap());
+        let mut filter = ScoreFilter::new(&mut stack, 0.5);
+
+        assert_eq!(
+            &(vec![(0, 10), (1000, 1100)], 2000),
+            filter.get_bad_part("A").unwrap()
+        );
+        assert_eq!(
+            &(vec![(1990, 2000)], 2000),
+            filter.get_bad_part("B").unwrap()
+        );
+        assert_eq!(
+            editor::ReadType::NotBad,
+            filter.get_read_type("B", 0.8).unwrap()
+        );
+        assert_eq!(
+            editor::ReadType::Chimeric,
+            filter.get_read_type("C", 0.8).unwrap()
+        );
     }
 
     #[test]
-    fn failled_correctly_on_corrupt_yacrd() {
-        let mut report = NamedTempFile::new().expect("Can't create tmpfile");
+    fn score_filter_junctions() {
+        let report: &[u8] = b"Chimeric\tA\t2000\t10,0,10;100,1000,1100,0.900
+Chimeric\tB\t2000\t100,1000,1100,0.200;10,1990,2000
+";
 
-        writeln!(
-            report.as_file_mut(),
-            "NotBad	SRR8494940.65223	2706	1131,0,1131;16,2690,2706
-NotCovered	SRR8494940.141626	30116	326,0,326;27159,2957,30116
-Chimeric	SRR8494940.91655	15691	151,0,151;4056,7213,11269;58,156"
-        )
-        .unwrap();
+        let mut stack = FromReport::from_reader(Box::new(report), "report").unwrap();
+        let mut filter = ScoreFilter::new(&mut stack, 0.5);
 
-        let stack = FromReport::new(report.into_temp_path().to_str().unwrap());
+        assert_eq!(
+            &(vec![(1990, 2000)], 2000),
+            filter.get_bad_part("B").unwrap()
+        );
+        assert_eq!(
+            vec![None],
+            filter
+                .get_junctions("B")
+                .unwrap()
+                .iter()
+                .map(|j| j.score)
+                .collect::<Vec<Option<f64>>>()
+        );
+        assert!(filter.get_junctions("A").is_none());
 
-        if !stack.is_err() {
-            assert!(false);
+        let (bads, _) = filter.get_bad_part("A").unwrap().clone();
+        let junctions = filter.get_junctions("A").unwrap();
+        assert_eq!(bads.len(), junctions.len());
+        assert_eq!(
+            vec![None, Some(0.9)],
+            junctions
+                .iter()
+                .map(|j| j.score)
+                .collect::<Vec<Option<f64>>>()
+        );
+    }
+
+    #[test]
+    fn breakpoint() {
+        assert_eq!(None, FromOverlap::breakpoint(Vec::new()));
+        assert_eq!(Some((10, (10, 10))), FromOverlap::breakpoint(vec![10]));
+        assert_eq!(
+            Some((1010, (1000, 1030))),
+            FromOverlap::breakpoint(vec![1030, 400, 1000, 1010, 1005, 1020, 450])
+        );
+    }
+
+    fn parts_ovls() -> Vec<(u32, u32)> {
+        let mut ovls = vec![(0, 1100), (0, 1200)];
+        for end in [995, 1000, 1000, 1005, 1010] {
+            ovls.push((0, end));
+        }
+        for begin in [1280, 1300, 1300, 1310] {
+            ovls.push((begin, 3000));
         }
+        ovls.push((1250, 3000));
+
+        ovls
     }
 
     #[test]
-    fn perfect_read_in_report() {
-        let mut report = NamedTempFile::new().expect("Can't create tmpfile");
+    fn refine_junction() {
+        let ovls = parts_ovls();
 
-        writeln!(report.as_file_mut(), "NotBad	perfect	2706	")
-            .expect("Error durring write of report in temp file");
+        let params = DetectionParams {
+            refine_junction: true,
+            ..DetectionParams::new(0)
+        };
 
-        let mut stack = FromReport::new(report.into_temp_path().to_str().unwrap())
-            .expect("Error when create stack object");
+        let parts = FromOverlap::compute_parts(ovls, 3000, &params);
 
+        assert_eq!(vec![(1000, 1300)], parts.bads);
         assert_eq!(
-            ["perfect".to_string()]
-                .iter()
-                .cloned()
-                .collect::<rustc_hash::FxHashSet<String>>(),
-            stack.get_reads()
+            vec![Junction {
+                score: None,
+                begin_ci: Some((995, 1010)),
+                end_ci: Some((1250, 1310)),
+            }],
+            parts.junctions
         );
 
-        assert_eq!(&(vec![], 2706), stack.get_bad_part("perfect").unwrap());
+        /* score is computed on refined region */
+        let scored = DetectionParams {
+            junction_score: true,
+            ..params
+        };
+        let ovls = parts_ovls();
+        let profile = FromOverlap::coverage_profile(&ovls, 3000);
+        let parts = FromOverlap::compute_parts(ovls.clone(), 3000, &scored);
+
+        assert_eq!(vec![(1000, 1300)], parts.bads);
+        assert_eq!(
+            Some(FromOverlap::junction_score(&ovls, &profile, (1000, 1300))),
+            parts.junctions[0].score
+        );
+    }
+
+    #[test]
+    fn merge_refined() {
+        let ci = |x: u32| Some((x, x + 10));
+        let mut bads = vec![(1250, 1600), (1000, 1300), (2000, 2005)];
+        let mut junctions = bads
+            .iter()
+            .map(|(begin, end)| Junction {
+                score: None,
+                begin_ci: ci(*begin),
+                end_ci: ci(*end),
+            })
+            .collect::<Vec<Junction>>();
+
+        let params = DetectionParams {
+            min_length: 10,
+            ..DetectionParams::new(0)
+        };
+        FromOverlap::merge_refined(&mut bads, &mut junctions, &params);
+
+        assert_eq!(vec![(1000, 1600)], bads);
+        assert_eq!(
+            vec![Junction {
+                score: None,
+                begin_ci: ci(1000),
+                end_ci: ci(1600),
+            }],
+            junctions
+        );
+    }
+
+    #[test]
+    fn junction_in_report() {
+        let report: &[u8] = b"Chimeric\tA\t2000\t10,0,10;100,1000,1100,0.900,990:1010:1090:1120
+Chimeric\tB\t2000\t100,1000,1100,980:1000:1100:1100
+";
+
+        let stack = FromReport::from_reader(Box::new(report), "report").unwrap();
+
+        assert_eq!(
+            Some(
+                &[
+                    Junction::default(),
+                    Junction {
+                        score: Some(0.9),
+                        begin_ci: Some((990, 1010)),
+                        end_ci: Some((1090, 1120)),
+                    }
+                ][..]
+            ),
+            stack.get_junctions("A")
+        );
+        assert_eq!(
+            Some(
+                &[Junction {
+                    score: None,
+                    begin_ci: Some((980, 1000)),
+                    end_ci: Some((1100, 1100)),
+                }][..]
+            ),
+            stack.get_junctions("B")
+        );
+
+        let report: &[u8] = b"Chimeric\tA\t2000\t100,1000,1100,1:2:3\n";
+        assert!(FromReport::from_reader(Box::new(report), "report").is_err());
     }
 }
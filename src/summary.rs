@@ -0,0 +1,344 @@
+/*
+Copyright (c) 2019 Pierre Marijon <pmarijon@mpi-inf.mpg.de>
+
+Permission is hereby granted, free of charge, to any person obtaining a copy
+of this software and associated documentation files (the "Software"), to deal
+in the Software without restriction, including without limitation the rights
+to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+copies of the Software, and to permit persons to whom the Software is
+furnished to do so, subject to the following conditions:
+
+The above copyright notice and this permission notice shall be included in all
+copies or substantial portions of the Software.
+
+THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+SOFTWARE.
+ */
+
+//! Summary statistics of a run: reads and bases by read type, bad region distributions and read
+//! lengths before and after sequence edition
+
+/* crate use */
+use anyhow::{Context, Result};
+
+/* local use */
+use crate::editor;
+use crate::error;
+use crate::util;
+
+/// Upper bounds of bad region length bins, last bin contains longer regions
+pub const LENGTH_BINS: [u32; 5] = [10, 100, 1000, 10000, 100000];
+
+/// Number of bins of bad region relative position in read
+pub const POSITION_BINS: usize = 10;
+
+/// Format of summary file
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SummaryFormat {
+    /// one json object
+    Json,
+    /// one `key value` line by statistic
+    Tsv,
+}
+
+impl std::str::FromStr for SummaryFormat {
+    type Err = String;
+
+    fn from_str(name: &str) -> std::result::Result<Self, Self::Err> {
+        match name.to_lowercase().as_str() {
+            "json" => Ok(SummaryFormat::Json),
+            "tsv" => Ok(SummaryFormat::Tsv),
+            _ => Err(format!(
+                "unknown summary format {}, possible values are json or tsv",
+                name
+            )),
+        }
+    }
+}
+
+/// Statistics aggregated during a run
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct Summary {
+    /// number of reads and bases by read type
+    pub classes: std::collections::BTreeMap<&'static str, (u64, u64)>,
+    /// number of bad regions at begin, in middle and at end of reads
+    pub positions: (u64, u64, u64),
+    /// number of bad regions in each bin of [LENGTH_BINS]
+    pub lengths: [u64; LENGTH_BINS.len() + 1],
+    /// number of bad regions in middle of reads by relative position of their center
+    pub middle_positions: [u64; POSITION_BINS],
+    /// number of sequences without any overlap
+    pub no_overlap: u64,
+    /// length of sequences read by editor
+    pub before: Vec<u32>,
+    /// length of sequences written by editor
+    pub after: Vec<u32>,
+}
+
+impl Summary {
+    /// Create an empty summary
+    pub fn new() -> Self {
+        Summary::default()
+    }
+
+    /// Add a read of report with its type, its length and its bad regions
+    pub fn add_read(
+        &mut self,
+        readtype: &editor::ReadType,
+        length: usize,
+        badregions: &[(u32, u32)],
+    ) {
+        let class = self.classes.entry(readtype.as_str()).or_default();
+        class.0 += 1;
+        class.1 += length as u64;
+
+        for (begin, end) in badregions {
+            let bin = LENGTH_BINS
+                .iter()
+                .position(|max| end - begin < *max)
+                .unwrap_or(LENGTH_BINS.len());
+            self.lengths[bin] += 1;
+
+            if *begin == 0 {
+                self.positions.0 += 1;
+            } else if *end as usize >= length {
+                self.positions.2 += 1;
+            } else {
+                self.positions.1 += 1;
+
+                let center = (*begin as f64 + *end as f64) / 2.0 / length as f64;
+                let bin = ((center * POSITION_BINS as f64) as usize).min(POSITION_BINS - 1);
+                self.middle_positions[bin] += 1;
+            }
+        }
+    }
+
+    /// Add a sequence read by editor, `known` is false if detection didn't see any overlap of it
+    pub fn add_input(&mut self, length: usize, known: bool) {
+        self.before.push(length as u32);
+        if !known {
+            self.no_overlap += 1;
+        }
+    }
+
+    /// Add a sequence written by editor
+    pub fn add_output(&mut self, length: usize) {
+        self.after.push(length as u32);
+    }
+
+    /// Compute N50 of lengths, 0 if lengths is empty
+    pub fn n50(lengths: &[u32]) -> u64 {
+        let mut sorted = lengths.to_vec();
+        sorted.sort_unstable_by(|a, b| b.cmp(a));
+
+        let total: u64 = sorted.iter().map(|l| *l as u64).sum();
+        let mut acc = 0;
+        for length in sorted {
+            acc += length as u64;
+            if acc * 2 >= total {
+                return length as u64;
+            }
+        }
+
+        0
+    }
+
+    /// Get statistics as a json value, `no_overlap` and `sequences` are present only if an editor
+    /// read a sequence file
+    pub fn to_json(&self) -> serde_json::Value {
+        let mut classes = serde_json::Map::new();
+        for readtype in [
+            editor::ReadType::Chimeric,
+            editor::ReadType::NotCovered,
+            editor::ReadType::Foldback,
+            editor::ReadType::Repeat,
+            editor::ReadType::NotBad,
+        ] {
+            let (reads, bases) = self
+                .classes
+                .get(readtype.as_str())
+                .cloned()
+                .unwrap_or_default();
+            classes.insert(
+                readtype.as_str().to_string(),
+                serde_json::json!({"reads": reads, "bases": bases}),
+            );
+        }
+
+        let mut lengths = serde_json::Map::new();
+        for (i, count) in self.lengths.iter().enumerate() {
+            let min = if i == 0 { 0 } else { LENGTH_BINS[i - 1] };
+            let label = match LENGTH_BINS.get(i) {
+                Some(max) => format!("{}-{}", min, max),
+                None => format!("{}-", min),
+            };
+            lengths.insert(label, (*count).into());
+        }
+
+        let mut middle_positions = serde_json::Map::new();
+        let step = 100 / POSITION_BINS;
+        for (i, count) in self.middle_positions.iter().enumerate() {
+            middle_positions.insert(format!("{}-{}%", i * step, (i + 1) * step), (*count).into());
+        }
+
+        let sequences = |lengths: &[u32]| {
+            serde_json::json!({
+                "reads": lengths.len(),
+                "bases": lengths.iter().map(|l| *l as u64).sum::<u64>(),
+                "n50": Summary::n50(lengths),
+            })
+        };
+
+        let mut json = serde_json::json!({
+            "classes": classes,
+            "bad_regions": {
+                "begin": self.positions.0,
+                "middle": self.positions.1,
+                "end": self.positions.2,
+                "length": lengths,
+                "middle_position": middle_positions,
+            },
+        });
+
+        if !self.before.is_empty() {
+            json["no_overlap"] = self.no_overlap.into();
+            json["sequences"] = serde_json::json!({
+                "before": sequences(&self.before),
+                "after": sequences(&self.after),
+            });
+        }
+
+        json
+    }
+
+    /// Write summary in `format`
+    pub fn write<W: std::io::Write>(&self, format: SummaryFormat, out: &mut W) -> Result<()> {
+        let json = self.to_json();
+
+        match format {
+            SummaryFormat::Json => {
+                serde_json::to_writer_pretty(&mut *out, &json).with_context(|| {
+                    error::Error::WritingErrorNoFilename {
+                        format: util::FileType::Yacrd,
+                    }
+                })?;
+                writeln!(out)
+            }
+            SummaryFormat::Tsv => {
+                let mut lines = Vec::new();
+                flatten("", &json, &mut lines);
+                lines
+                    .iter()
+                    .try_for_each(|(key, value)| writeln!(out, "{}\t{}", key, value))
+            }
+        }
+        .with_context(|| error::Error::WritingErrorNoFilename {
+            format: util::FileType::Yacrd,
+        })
+    }
+}
+
+fn flatten(prefix: &str, value: &serde_json::Value, lines: &mut Vec<(String, String)>) {
+    match value {
+        serde_json::Value::Object(map) => {
+            for (key, value) in map {
+                let key = if prefix.is_empty() {
+                    key.to_string()
+                } else {
+                    format!("{}.{}", prefix, key)
+                };
+                flatten(&key, value, lines);
+            }
+        }
+        _ => lines.push((prefix.to_string(), value.to_string())),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn n50() {
+        assert_eq!(0, Summary::n50(&[]));
+        assert_eq!(10, Summary::n50(&[10]));
+        assert_eq!(8, Summary::n50(&[2, 3, 4, 5, 6, 7, 8, 9, 10]));
+    }
+
+    #[test]
+    fn add_read() {
+        let mut summary = Summary::new();
+
+        summary.add_read(
+            &editor::ReadType::Chimeric,
+            1000,
+            &[(0, 5), (100, 150), (200, 2000), (900, 1000)],
+        );
+        summary.add_read(&editor::ReadType::NotBad, 500, &[]);
+        summary.add_read(&editor::ReadType::NotBad, 300, &[(0, 300)]);
+
+        assert_eq!(Some(&(1, 1000)), summary.classes.get("Chimeric"));
+        assert_eq!(Some(&(2, 800)), summary.classes.get("NotBad"));
+        assert_eq!((2, 1, 2), summary.positions);
+        assert_eq!([1, 1, 2, 1, 0, 0], summary.lengths);
+        assert_eq!([0, 1, 0, 0, 0, 0, 0, 0, 0, 0], summary.middle_positions);
+    }
+
+    #[test]
+    fn write() {
+        let mut summary = Summary::new();
+
+        summary.add_read(&editor::ReadType::Chimeric, 1000, &[(450, 550)]);
+        summary.add_input(1000, true);
+        summary.add_input(200, false);
+        summary.add_output(450);
+        summary.add_output(450);
+        summary.add_output(200);
+
+        let json = summary.to_json();
+        assert_eq!(1, json["classes"]["Chimeric"]["reads"]);
+        assert_eq!(0, json["classes"]["NotBad"]["reads"]);
+        assert_eq!(1, json["no_overlap"]);
+        assert_eq!(1, json["bad_regions"]["length"]["100-1000"]);
+        assert_eq!(1, json["bad_regions"]["middle_position"]["50-60%"]);
+        assert_eq!(1000, json["sequences"]["before"]["n50"]);
+        assert_eq!(450, json["sequences"]["after"]["n50"]);
+
+        let mut out = Vec::new();
+        summary.write(SummaryFormat::Tsv, &mut out).unwrap();
+        let tsv = String::from_utf8(out).unwrap();
+        assert!(tsv.contains("classes.Chimeric.bases\t1000\n"));
+        assert!(tsv.contains("bad_regions.middle\t1\n"));
+        assert!(tsv.contains("sequences.after.reads\t3\n"));
+
+        let mut out = Vec::new();
+        summary.write(SummaryFormat::Json, &mut out).unwrap();
+        assert_eq!(
+            json,
+            serde_json::from_slice::<serde_json::Value>(&out).unwrap()
+        );
+    }
+
+    #[test]
+    fn without_editor() {
+        let mut summary = Summary::new();
+        summary.add_read(&editor::ReadType::NotBad, 1000, &[]);
+
+        let json = summary.to_json();
+        assert_eq!(1, json["classes"]["NotBad"]["reads"]);
+        assert!(json.get("no_overlap").is_none());
+        assert!(json.get("sequences").is_none());
+    }
+
+    #[test]
+    fn summary_format() {
+        assert_eq!(Ok(SummaryFormat::Json), "json".parse());
+        assert_eq!(Ok(SummaryFormat::Tsv), "TSV".parse());
+        assert!("csv".parse::<SummaryFormat>().is_err());
+    }
+}
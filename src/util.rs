@@ -20,73 +20,278 @@ OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
 SOFTWARE.
  */
 
+//! Miscellaneous helpers: file type detection, file opening and number parsing
+
+/* std use */
+use std::io::{BufRead, Read};
+
 /* crate use */
 use anyhow::{anyhow, Context, Result};
 
 /* local use */
 use crate::error;
 
-#[derive(Debug, PartialEq)]
+/// Filename used for standard input or standard output
+pub const STD_STREAM: &str = "-";
+
+/// Type of file yacrd can read or write
+#[derive(Debug, Clone, Copy, PartialEq)]
 pub enum FileType {
+    /// sequence in fasta format
     Fasta,
+    /// sequence in fastq format
     Fastq,
+    /// yacrd report
     Yacrd,
+    /// overlap in Pairwise mApping Format
     Paf,
+    /// overlap in blasr m4 format (mhap output)
     M4,
+    /// yacrd binary overlap file
     YacrdOverlap,
+    /// alignment in SAM format
+    Sam,
+    /// alignment in BAM format
+    Bam,
 }
 
-pub fn get_file_type(filename: &str) -> Option<FileType> {
-    if filename.contains(".m4") || filename.contains(".mhap") {
-        Some(FileType::M4)
-    } else if filename.contains(".paf") {
-        Some(FileType::Paf)
-    } else if filename.contains(".yacrd") {
-        Some(FileType::Yacrd)
-    } else if filename.contains(".fastq") || filename.contains(".fq") {
-        Some(FileType::Fastq)
-    } else if filename.contains(".fasta") || filename.contains(".fa") {
-        Some(FileType::Fasta)
-    } else if filename.contains(".yovl") {
-        Some(FileType::YacrdOverlap)
-    } else {
-        None
+impl std::str::FromStr for FileType {
+    type Err = String;
+
+    fn from_str(name: &str) -> std::result::Result<Self, Self::Err> {
+        match name.to_lowercase().as_str() {
+            "fasta" | "fa" => Ok(FileType::Fasta),
+            "fastq" | "fq" => Ok(FileType::Fastq),
+            "yacrd" => Ok(FileType::Yacrd),
+            "paf" => Ok(FileType::Paf),
+            "m4" | "mhap" => Ok(FileType::M4),
+            "yovl" => Ok(FileType::YacrdOverlap),
+            "sam" => Ok(FileType::Sam),
+            "bam" => Ok(FileType::Bam),
+            _ => Err(format!(
+                "unknown format {}, possible values are fasta, fastq, yacrd, paf, m4, yovl, sam or bam",
+                name
+            )),
+        }
+    }
+}
+
+/// Detect type of file from extensions present in name of file, the last recognized extension is used
+pub(crate) fn get_file_type(filename: &str) -> Option<FileType> {
+    let name = std::path::Path::new(filename).file_name()?.to_str()?;
+
+    name.split_once('.')?
+        .1
+        .rsplit('.')
+        .find_map(|ext| match ext {
+            "m4" | "mhap" => Some(FileType::M4),
+            "paf" => Some(FileType::Paf),
+            "yacrd" | "jsonl" => Some(FileType::Yacrd),
+            "fastq" | "fq" => Some(FileType::Fastq),
+            "fasta" | "fa" => Some(FileType::Fasta),
+            "yovl" => Some(FileType::YacrdOverlap),
+            "sam" => Some(FileType::Sam),
+            "bam" => Some(FileType::Bam),
+            _ => None,
+        })
+}
+
+/// Detect type of file from its first bytes (after decompression)
+pub(crate) fn sniff_file_type(buffer: &[u8]) -> Option<FileType> {
+    if buffer.starts_with(b"YOVL") {
+        return Some(FileType::YacrdOverlap);
+    } else if buffer.starts_with(b"BAM\x01") {
+        return Some(FileType::Bam);
+    }
+
+    let line = buffer.split(|c| *c == b'\n').next()?;
+    let line = line.strip_suffix(b"\r").unwrap_or(line);
+
+    match line.first()? {
+        b'>' => return Some(FileType::Fasta),
+        /* yacrd report header line or jsonl report */
+        b'#' | b'{' => return Some(FileType::Yacrd),
+        b'@' => {
+            /* sam header line start with a two letters record type */
+            if line.len() > 3 && line[1..3].iter().all(u8::is_ascii_alphabetic) && line[3] == b'\t'
+            {
+                return Some(FileType::Sam);
+            } else {
+                return Some(FileType::Fastq);
+            }
+        }
+        _ => (),
+    }
+
+    let line = std::str::from_utf8(line).ok()?;
+    let is_number = |field: &str| !field.is_empty() && field.bytes().all(|c| c.is_ascii_digit());
+    let is_float = |field: &str| field.parse::<f64>().is_ok();
+
+    let fields = line.split('\t').collect::<Vec<&str>>();
+    if fields.len() >= 12
+        && [1, 2, 3, 6, 7, 8, 9, 10, 11]
+            .iter()
+            .all(|i| is_number(fields[*i]))
+        && (fields[4] == "+" || fields[4] == "-")
+    {
+        return Some(FileType::Paf);
+    } else if fields.len() >= 11 && [1, 3, 4].iter().all(|i| is_number(fields[*i])) {
+        return Some(FileType::Sam);
+    } else if fields.len() >= 4
+        && fields[0].bytes().all(|c| c.is_ascii_alphabetic())
+        && is_number(fields[2])
+    {
+        return Some(FileType::Yacrd);
+    }
+
+    let fields = line.split(' ').collect::<Vec<&str>>();
+    if fields.len() >= 12
+        && is_float(fields[2])
+        && [5, 6, 7, 9, 10, 11].iter().all(|i| is_number(fields[*i]))
+    {
+        return Some(FileType::M4);
+    }
+
+    None
+}
+
+/// Detect type of file, content of input is used first, if it fails filename is used.
+///
+/// First line of input is read to detect format, input is replaced by a reader that yields this line again.
+pub fn detect_file_type(input: &mut Box<dyn std::io::BufRead>, filename: &str) -> Option<FileType> {
+    let mut head = Vec::new();
+    let sniffed = match input.take(8192).read_until(b'\n', &mut head) {
+        Ok(_) => sniff_file_type(&head),
+        Err(_) => None,
+    };
+
+    let rest = std::mem::replace(input, Box::new(std::io::empty()));
+    *input = Box::new(std::io::Cursor::new(head).chain(rest));
+
+    sniffed.or_else(|| get_file_type(filename))
+}
+
+/// Crc32 of bytes read by a `Crc32Reader`, shared with the reader
+pub type SharedCrc32 = std::rc::Rc<std::cell::RefCell<crc32fast::Hasher>>;
+
+/// Reader that computes crc32 of all bytes read
+pub(crate) struct Crc32Reader<R> {
+    inner: R,
+    hasher: SharedCrc32,
+}
+
+impl<R> Crc32Reader<R> {
+    /// Wrap `inner`, crc32 of bytes read is available in returned [SharedCrc32]
+    pub fn new(inner: R) -> (Self, SharedCrc32) {
+        let hasher = SharedCrc32::default();
+
+        (
+            Crc32Reader {
+                inner,
+                hasher: hasher.clone(),
+            },
+            hasher,
+        )
+    }
+}
+
+impl<R: std::io::Read> std::io::Read for Crc32Reader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        let nb = self.inner.read(buf)?;
+        self.hasher.borrow_mut().update(&buf[..nb]);
+
+        Ok(nb)
     }
 }
 
-pub fn read_file(
+/// Like `read_file`, crc32 of raw file content is computed while file is read
+pub fn read_file_crc32(
     filename: &str,
     buffer_size: usize,
-) -> Result<(Box<dyn std::io::Read>, niffler::compression::Format)> {
-    let raw_in = Box::new(std::io::BufReader::with_capacity(
+) -> Result<(
+    Box<dyn std::io::BufRead>,
+    niffler::compression::Format,
+    SharedCrc32,
+)> {
+    let raw_in: Box<dyn std::io::Read> = if filename == STD_STREAM {
+        Box::new(std::io::stdin())
+    } else {
+        Box::new(
+            std::fs::File::open(filename).with_context(|| error::Error::CantReadFile {
+                filename: filename.to_string(),
+            })?,
+        )
+    };
+    let (raw_in, crc32) = Crc32Reader::new(raw_in);
+
+    let (input, compression) = niffler::get_reader(Box::new(std::io::BufReader::with_capacity(
         buffer_size,
-        std::fs::File::open(filename).with_context(|| error::Error::CantReadFile {
-            filename: filename.to_string(),
-        })?,
-    ));
+        raw_in,
+    )))
+    .with_context(|| anyhow!("Error in compression detection of file {}", filename))?;
+
+    Ok((
+        Box::new(std::io::BufReader::with_capacity(buffer_size, input)),
+        compression,
+        crc32,
+    ))
+}
 
-    niffler::get_reader(raw_in)
-        .with_context(|| anyhow!("Error in compression detection of file {}", filename))
+/// Open a file for reading, compression is autodetected, `-` is standard input
+pub(crate) fn read_file(
+    filename: &str,
+    buffer_size: usize,
+) -> Result<(Box<dyn std::io::BufRead>, niffler::compression::Format)> {
+    let raw_in: Box<dyn std::io::Read> = if filename == STD_STREAM {
+        Box::new(std::io::BufReader::with_capacity(
+            buffer_size,
+            std::io::stdin(),
+        ))
+    } else {
+        Box::new(std::io::BufReader::with_capacity(
+            buffer_size,
+            std::fs::File::open(filename).with_context(|| error::Error::CantReadFile {
+                filename: filename.to_string(),
+            })?,
+        ))
+    };
+
+    let (input, compression) = niffler::get_reader(raw_in)
+        .with_context(|| anyhow!("Error in compression detection of file {}", filename))?;
+
+    Ok((
+        Box::new(std::io::BufReader::with_capacity(buffer_size, input)),
+        compression,
+    ))
 }
 
+/// Create a file for writing with the given compression, `-` is standard output
 pub fn write_file(
     filename: &str,
     compression: niffler::compression::Format,
     buffer_size: usize,
 ) -> Result<Box<dyn std::io::Write>> {
-    let raw_out = Box::new(std::io::BufWriter::with_capacity(
-        buffer_size,
-        std::fs::File::create(filename).with_context(|| error::Error::CantWriteFile {
-            filename: filename.to_string(),
-        })?,
-    ));
+    let raw_out: Box<dyn std::io::Write> = if filename == STD_STREAM {
+        Box::new(std::io::BufWriter::with_capacity(
+            buffer_size,
+            std::io::stdout(),
+        ))
+    } else {
+        Box::new(std::io::BufWriter::with_capacity(
+            buffer_size,
+            std::fs::File::create(filename).with_context(|| error::Error::CantWriteFile {
+                filename: filename.to_string(),
+            })?,
+        ))
+    };
 
     let output = niffler::get_writer(raw_out, compression, niffler::compression::Level::One)?;
 
     Ok(output)
 }
 
-pub fn str2usize(val: &str) -> Result<usize> {
+/// Parse a usize from a string
+pub(crate) fn str2usize(val: &str) -> Result<usize> {
     val.parse::<usize>().with_context(|| {
         anyhow!(
             "Error during parsing of number from string {:?} in usize",
@@ -95,7 +300,8 @@ pub fn str2usize(val: &str) -> Result<usize> {
     })
 }
 
-pub fn str2u32(val: &str) -> Result<u32> {
+/// Parse a u32 from a string
+pub(crate) fn str2u32(val: &str) -> Result<u32> {
     val.parse::<u32>().with_context(|| {
         anyhow!(
             "Error during parsing of number from string {:?} in u32",
@@ -104,6 +310,7 @@ pub fn str2u32(val: &str) -> Result<u32> {
     })
 }
 
+/// Parse a u64 from a string
 pub fn str2u64(val: &str) -> Result<u64> {
     val.parse::<u64>().with_context(|| {
         anyhow!(
@@ -114,6 +321,7 @@ pub fn str2u64(val: &str) -> Result<u64> {
 }
 
 #[cfg(test)]
+#[allow(clippy::assertions_on_constants)]
 mod tests {
     use super::*;
 
@@ -317,5 +525,163 @@ mod tests {
         fn yacrd_overlap_with_nopoint() {
             assert_eq!(None, get_file_type("yovl.other_ext"));
         }
+
+        #[test]
+        fn sam() {
+            assert_eq!(Some(FileType::Sam), get_file_type("test.sam"));
+        }
+
+        #[test]
+        fn sam_with_nopoint() {
+            assert_eq!(None, get_file_type("sam.other_ext"));
+        }
+
+        #[test]
+        fn bam() {
+            assert_eq!(Some(FileType::Bam), get_file_type("test.bam"));
+        }
+
+        #[test]
+        fn bam_with_nopoint() {
+            assert_eq!(None, get_file_type("bam.other_ext"));
+        }
+
+        #[test]
+        fn extension_prefix() {
+            assert_eq!(
+                Some(FileType::Fastq),
+                get_file_type("reads.fastq.fa_backup")
+            );
+            assert_eq!(None, get_file_type("reads.sample_fa"));
+        }
+
+        #[test]
+        fn last_extension() {
+            assert_eq!(Some(FileType::Yacrd), get_file_type("reads.paf.yacrd"));
+            assert_eq!(Some(FileType::Yacrd), get_file_type("reads.jsonl.gz"));
+            assert_eq!(Some(FileType::Paf), get_file_type("reads.fasta.paf.gz"));
+        }
+
+        #[test]
+        fn directory() {
+            assert_eq!(None, get_file_type("data.paf/reads"));
+            assert_eq!(Some(FileType::Fasta), get_file_type("data.paf/reads.fasta"));
+        }
+
+        #[test]
+        fn from_str() {
+            assert_eq!(Ok(FileType::Paf), "paf".parse::<FileType>());
+            assert_eq!(Ok(FileType::YacrdOverlap), "yovl".parse::<FileType>());
+            assert_eq!(Ok(FileType::Fastq), "FASTQ".parse::<FileType>());
+            assert!("other".parse::<FileType>().is_err());
+        }
+    }
+
+    mod sniff {
+        use super::*;
+
+        #[test]
+        fn fasta() {
+            assert_eq!(Some(FileType::Fasta), sniff_file_type(b">1\nACTG\n"));
+        }
+
+        #[test]
+        fn fastq() {
+            assert_eq!(
+                Some(FileType::Fastq),
+                sniff_file_type(b"@1\nACTG\n+\n????\n")
+            );
+        }
+
+        #[test]
+        fn paf() {
+            assert_eq!(
+                Some(FileType::Paf),
+                sniff_file_type(b"1\t12000\t20\t4500\t-\t2\t10000\t5500\t10000\t4500\t4500\t255\n")
+            );
+        }
+
+        #[test]
+        fn m4() {
+            assert_eq!(
+                Some(FileType::M4),
+                sniff_file_type(b"1 2 0.1 2 0 20 4500 12000 0 5500 10000 10000\n")
+            );
+        }
+
+        #[test]
+        fn yacrd() {
+            assert_eq!(
+                Some(FileType::Yacrd),
+                sniff_file_type(b"NotBad\tSRR8494940.65223\t2706\t1131,0,1131;16,2690,2706\n")
+            );
+            assert_eq!(
+                Some(FileType::Yacrd),
+                sniff_file_type(b"NotBad\tperfect\t2706\t\n")
+            );
+            assert_eq!(
+                Some(FileType::Yacrd),
+                sniff_file_type(b"# coverage threshold: 2\nNotBad\tperfect\t2706\t\n")
+            );
+            assert_eq!(
+                Some(FileType::Yacrd),
+                sniff_file_type(b"{\"type\":\"NotBad\",\"name\":\"perfect\",\"length\":2706,\"bad_regions\":[]}\n")
+            );
+        }
+
+        #[test]
+        fn sam() {
+            assert_eq!(
+                Some(FileType::Sam),
+                sniff_file_type(b"@HD\tVN:1.6\n@SQ\tSN:2\tLN:10000\n")
+            );
+            assert_eq!(
+                Some(FileType::Sam),
+                sniff_file_type(b"1\t16\t2\t5501\t60\t4480M\t*\t0\t0\t*\t*\n")
+            );
+        }
+
+        #[test]
+        fn binary() {
+            assert_eq!(Some(FileType::Bam), sniff_file_type(b"BAM\x01\x00\x00"));
+            assert_eq!(
+                Some(FileType::YacrdOverlap),
+                sniff_file_type(b"YOVL\x01\x00\x00")
+            );
+        }
+
+        #[test]
+        fn unknown() {
+            assert_eq!(None, sniff_file_type(b""));
+            assert_eq!(None, sniff_file_type(b"some text\n"));
+        }
+
+        #[test]
+        fn detect() {
+            let mut input: Box<dyn std::io::BufRead> = Box::new(&b">1\nACTG\n"[..]);
+            assert_eq!(
+                Some(FileType::Fasta),
+                detect_file_type(&mut input, "reads.paf")
+            );
+
+            let mut content = Vec::new();
+            input.read_to_end(&mut content).unwrap();
+            assert_eq!(b">1\nACTG\n".to_vec(), content);
+
+            let mut input: Box<dyn std::io::BufRead> = Box::new(&b""[..]);
+            assert_eq!(
+                Some(FileType::Paf),
+                detect_file_type(&mut input, "reads.paf")
+            );
+        }
+    }
+    #[test]
+    fn crc32_reader() {
+        let (mut reader, crc32) = Crc32Reader::new(&b"123456789"[..]);
+
+        let mut content = Vec::new();
+        reader.read_to_end(&mut content).unwrap();
+
+        assert_eq!(0xcbf43926, crc32.borrow().clone().finalize());
     }
 }
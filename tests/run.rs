@@ -30,30 +30,18 @@ mod tests {
 
     use super::*;
 
-    fn diff_unorder(truth_path: &str, result_path: &str) {
-        let truth_file = std::io::BufReader::new(
-            std::fs::File::open(truth_path).expect(&format!("Impossible to open {}", truth_path)),
-        );
-
-        let mut truth: std::collections::HashSet<String> = std::collections::HashSet::new();
-
-        for res in truth_file.lines() {
-            let line = res.unwrap();
-            truth.insert(line);
-        }
-
-        let result_file = std::io::BufReader::new(
-            std::fs::File::open(result_path).expect(&format!("Impossible to open {}", result_path)),
-        );
-
-        let mut result: std::collections::HashSet<String> = std::collections::HashSet::new();
-
-        for res in result_file.lines() {
-            let line = res.unwrap();
-            result.insert(line);
-        }
-
-        if truth != result {
+    fn diff_report(truth_path: &str, result_path: &str) {
+        let report_lines = |path: &str| -> Vec<String> {
+            std::io::BufReader::new(
+                std::fs::File::open(path).unwrap_or_else(|_| panic!("Impossible to open {}", path)),
+            )
+            .lines()
+            .map(|res| res.unwrap())
+            .filter(|line| !line.starts_with('#'))
+            .collect()
+        };
+
+        if report_lines(truth_path) != report_lines(result_path) {
             panic!(
                 "Truth {} and result {} are different",
                 truth_path, result_path
@@ -63,7 +51,8 @@ mod tests {
 
     fn diff(truth_path: &str, result_path: &str) {
         let truth_file = std::io::BufReader::new(
-            std::fs::File::open(truth_path).expect(&format!("Impossible to open {}", truth_path)),
+            std::fs::File::open(truth_path)
+                .unwrap_or_else(|_| panic!("Impossible to open {}", truth_path)),
         );
 
         let mut truth: Vec<String> = Vec::new();
@@ -74,7 +63,8 @@ mod tests {
         }
 
         let result_file = std::io::BufReader::new(
-            std::fs::File::open(result_path).expect(&format!("Impossible to open {}", result_path)),
+            std::fs::File::open(result_path)
+                .unwrap_or_else(|_| panic!("Impossible to open {}", result_path)),
         );
 
         let mut result: Vec<String> = Vec::new();
@@ -95,7 +85,246 @@ mod tests {
     #[test]
     fn detection() {
         let mut child = Command::new("./target/debug/yacrd")
-            .args(&["-i", "tests/reads.paf", "-o", "tests/result.yacrd"])
+            .args(["-i", "tests/reads.paf", "-o", "tests/result.yacrd"])
+            .stderr(Stdio::piped())
+            .stdout(Stdio::piped())
+            .spawn()
+            .expect("Couldn't create yacrd subprocess");
+
+        if !child.wait().expect("Error durring yacrd run").success() {
+            let mut stdout = String::new();
+            let mut stderr = String::new();
+
+            child.stdout.unwrap().read_to_string(&mut stdout).unwrap();
+            child.stderr.unwrap().read_to_string(&mut stderr).unwrap();
+
+            println!("stdout: {}", stdout);
+            println!("stderr: {}", stderr);
+            panic!();
+        }
+
+        diff_report("tests/truth.yacrd", "tests/result.yacrd");
+    }
+
+    #[test]
+    fn detection_threads() {
+        let status = Command::new("./target/debug/yacrd")
+            .args([
+                "-i",
+                "tests/reads.paf",
+                "-o",
+                "tests/result.threads.yacrd",
+                "-t",
+                "4",
+            ])
+            .stderr(Stdio::piped())
+            .stdout(Stdio::piped())
+            .status()
+            .expect("Couldn't create yacrd subprocess");
+        assert!(status.success());
+
+        diff_report("tests/truth.yacrd", "tests/result.threads.yacrd");
+    }
+
+    #[test]
+    fn report_header() {
+        let status = Command::new("./target/debug/yacrd")
+            .args([
+                "-i",
+                "tests/reads.paf",
+                "-o",
+                "tests/result.header.yacrd",
+                "-c",
+                "2",
+                "-t",
+                "2",
+            ])
+            .stderr(Stdio::piped())
+            .stdout(Stdio::piped())
+            .status()
+            .expect("Couldn't create yacrd subprocess");
+        assert!(status.success());
+
+        let header: Vec<String> =
+            std::io::BufReader::new(std::fs::File::open("tests/result.header.yacrd").unwrap())
+                .lines()
+                .map(|l| l.unwrap())
+                .take_while(|l| l.starts_with('#'))
+                .collect();
+
+        assert_eq!("# format: yacrd-report 2", header[0]);
+        assert!(header.contains(&"# param: coverage=2".to_string()));
+        assert!(header.contains(&"# param: not-coverage=0.8".to_string()));
+        assert!(header
+            .iter()
+            .any(|l| l == "# input: tests/reads.paf crc32:70884a39"));
+
+        let command = header
+            .iter()
+            .find(|l| l.starts_with("# command: "))
+            .unwrap();
+        assert!(command.ends_with("-i tests/reads.paf -c 2"));
+    }
+
+    #[test]
+    fn detection_stdin_stdout() {
+        let child = Command::new("./target/debug/yacrd")
+            .args(["-i", "-", "-o", "-"])
+            .stdin(std::fs::File::open("tests/reads.paf").unwrap())
+            .stderr(Stdio::piped())
+            .stdout(Stdio::piped())
+            .spawn()
+            .expect("Couldn't create yacrd subprocess");
+
+        let output = child.wait_with_output().expect("Error durring yacrd run");
+        if !output.status.success() {
+            println!("stderr: {}", String::from_utf8_lossy(&output.stderr));
+            panic!();
+        }
+
+        assert!(String::from_utf8_lossy(&output.stdout)
+            .lines()
+            .any(|l| l == "# input: - crc32:70884a39"));
+
+        std::fs::write("tests/result.stdout.yacrd", output.stdout).unwrap();
+
+        diff_report("tests/truth.yacrd", "tests/result.stdout.yacrd");
+    }
+
+    #[test]
+    fn streaming_stdin_crc32() {
+        let child = Command::new("./target/debug/yacrd")
+            .args(["-i", "-", "-o", "-", "--streaming"])
+            .stdin(std::fs::File::open("tests/reads.paf").unwrap())
+            .stderr(Stdio::piped())
+            .stdout(Stdio::piped())
+            .spawn()
+            .expect("Couldn't create yacrd subprocess");
+
+        let output = child.wait_with_output().expect("Error durring yacrd run");
+        if !output.status.success() {
+            println!("stderr: {}", String::from_utf8_lossy(&output.stderr));
+            panic!();
+        }
+
+        let report = String::from_utf8_lossy(&output.stdout);
+        assert!(report.lines().any(|l| l == "# input: -"));
+        assert_eq!(Some("# input: - crc32:70884a39"), report.lines().last());
+    }
+
+    #[test]
+    fn split_stdin_stdout() {
+        let child = Command::new("./target/debug/yacrd")
+            .args([
+                "-i",
+                "tests/reads.paf",
+                "-o",
+                "tests/result.split_stdin.yacrd",
+                "split",
+                "-i",
+                "-",
+                "-o",
+                "-",
+            ])
+            .stdin(std::fs::File::open("tests/reads.fastq").unwrap())
+            .stderr(Stdio::piped())
+            .stdout(Stdio::piped())
+            .spawn()
+            .expect("Couldn't create yacrd subprocess");
+
+        let output = child.wait_with_output().expect("Error durring yacrd run");
+        if !output.status.success() {
+            println!("stderr: {}", String::from_utf8_lossy(&output.stderr));
+            panic!();
+        }
+
+        std::fs::write("tests/reads.split_stdout.fastq", output.stdout).unwrap();
+
+        diff_report("tests/truth.yacrd", "tests/result.split_stdin.yacrd");
+        diff("tests/truth.split.fastq", "tests/reads.split_stdout.fastq");
+    }
+
+    #[test]
+    fn stdout_used_twice() {
+        let status = Command::new("./target/debug/yacrd")
+            .args([
+                "-i",
+                "tests/reads.paf",
+                "-o",
+                "-",
+                "split",
+                "-i",
+                "tests/reads.fastq",
+                "-o",
+                "-",
+            ])
+            .stderr(Stdio::piped())
+            .stdout(Stdio::piped())
+            .status()
+            .expect("Couldn't create yacrd subprocess");
+
+        assert!(!status.success());
+    }
+
+    #[test]
+    fn auto_coverage_ondisk() {
+        let status = Command::new("./target/debug/yacrd")
+            .args([
+                "-i",
+                "tests/reads.paf",
+                "-o",
+                "tests/result.auto_ondisk.yacrd",
+                "-c",
+                "auto",
+                "-d",
+                "tests/auto_ondisk",
+            ])
+            .stderr(Stdio::piped())
+            .stdout(Stdio::piped())
+            .status()
+            .expect("Couldn't create yacrd subprocess");
+
+        assert!(!status.success());
+    }
+
+    #[test]
+    fn convert() {
+        let mut child = Command::new("./target/debug/yacrd")
+            .args([
+                "-i",
+                "tests/reads.paf",
+                "-o",
+                "tests/result.convert.yacrd",
+                "convert",
+                "-o",
+                "tests/result.convert.yovl",
+            ])
+            .stderr(Stdio::piped())
+            .stdout(Stdio::piped())
+            .spawn()
+            .expect("Couldn't create yacrd subprocess");
+
+        if !child.wait().expect("Error durring yacrd run").success() {
+            let mut stdout = String::new();
+            let mut stderr = String::new();
+
+            child.stdout.unwrap().read_to_string(&mut stdout).unwrap();
+            child.stderr.unwrap().read_to_string(&mut stderr).unwrap();
+
+            println!("stdout: {}", stdout);
+            println!("stderr: {}", stderr);
+            panic!();
+        }
+
+        diff_report("tests/truth.yacrd", "tests/result.convert.yacrd");
+
+        let mut child = Command::new("./target/debug/yacrd")
+            .args([
+                "-i",
+                "tests/result.convert.yovl",
+                "-o",
+                "tests/result.yovl.yacrd",
+            ])
             .stderr(Stdio::piped())
             .stdout(Stdio::piped())
             .spawn()
@@ -113,14 +342,12 @@ mod tests {
             panic!();
         }
 
-        diff_unorder("tests/truth.yacrd", "tests/result.yacrd");
+        diff_report("tests/truth.yacrd", "tests/result.yovl.yacrd");
     }
 
     #[test]
     fn detection_ondisk() {
-        if cfg!(windows) {
-            ()
-        } else {
+        if !cfg!(windows) {
             if std::path::Path::new("tests/ondisk").exists() {
                 std::fs::remove_dir_all(std::path::Path::new("tests/ondisk"))
                     .expect("We can't delete temporary directory of ondisk test");
@@ -130,7 +357,7 @@ mod tests {
                 .expect("We can't create temporary directory for ondisk test");
 
             let mut child = Command::new("./target/debug/yacrd")
-                .args(&[
+                .args([
                     "-i",
                     "tests/reads.paf",
                     "-o",
@@ -155,14 +382,14 @@ mod tests {
                 panic!();
             }
 
-            diff_unorder("tests/truth.yacrd", "tests/result.ondisk.yacrd");
+            diff_report("tests/truth.yacrd", "tests/result.ondisk.yacrd");
         }
     }
 
     #[test]
     fn filter() {
         let mut child = Command::new("./target/debug/yacrd")
-            .args(&[
+            .args([
                 "-i",
                 "tests/reads.paf",
                 "-o",
@@ -190,14 +417,14 @@ mod tests {
             panic!();
         }
 
-        diff_unorder("tests/truth.yacrd", "tests/result.filter.yacrd");
+        diff_report("tests/truth.yacrd", "tests/result.filter.yacrd");
         diff("tests/truth.filter.fastq", "tests/reads.filter.fastq")
     }
 
     #[test]
     fn extract() {
         let mut child = Command::new("./target/debug/yacrd")
-            .args(&[
+            .args([
                 "-i",
                 "tests/reads.paf",
                 "-o",
@@ -225,14 +452,14 @@ mod tests {
             panic!();
         }
 
-        diff_unorder("tests/truth.yacrd", "tests/result.extract.yacrd");
+        diff_report("tests/truth.yacrd", "tests/result.extract.yacrd");
         diff("tests/truth.extract.fastq", "tests/reads.extract.fastq")
     }
 
     #[test]
     fn split() {
         let mut child = Command::new("./target/debug/yacrd")
-            .args(&[
+            .args([
                 "-i",
                 "tests/reads.paf",
                 "-o",
@@ -260,14 +487,205 @@ mod tests {
             panic!();
         }
 
-        diff_unorder("tests/truth.yacrd", "tests/result.split.yacrd");
+        diff_report("tests/truth.yacrd", "tests/result.split.yacrd");
         diff("tests/truth.split.fastq", "tests/reads.split.fastq")
     }
 
+    #[test]
+    fn jsonl_report() {
+        let status = Command::new("./target/debug/yacrd")
+            .args([
+                "-i",
+                "tests/reads.paf",
+                "-o",
+                "tests/result.jsonl",
+                "--report-format",
+                "jsonl",
+            ])
+            .stderr(Stdio::piped())
+            .stdout(Stdio::piped())
+            .status()
+            .expect("Couldn't create yacrd subprocess");
+        assert!(status.success());
+
+        let mut child = Command::new("./target/debug/yacrd")
+            .args([
+                "-i",
+                "tests/result.jsonl",
+                "-o",
+                "tests/result.jsonl.yacrd",
+                "split",
+                "-i",
+                "tests/reads.fastq",
+                "-o",
+                "tests/reads.jsonl.split.fastq",
+            ])
+            .stderr(Stdio::piped())
+            .stdout(Stdio::piped())
+            .spawn()
+            .expect("Couldn't create yacrd subprocess");
+
+        if !child.wait().expect("Error durring yacrd run").success() {
+            let mut stderr = String::new();
+            child.stderr.unwrap().read_to_string(&mut stderr).unwrap();
+
+            println!("stderr: {}", stderr);
+            panic!();
+        }
+
+        diff_report("tests/truth.yacrd", "tests/result.jsonl.yacrd");
+        diff("tests/truth.split.fastq", "tests/reads.jsonl.split.fastq")
+    }
+
+    #[test]
+    fn bed_bedgraph() {
+        let status = Command::new("./target/debug/yacrd")
+            .args([
+                "-i",
+                "tests/reads.paf",
+                "-o",
+                "tests/result.bed.yacrd",
+                "--report-format",
+                "bed",
+                "--bedgraph",
+                "tests/result.bedgraph.yacrd",
+            ])
+            .stderr(Stdio::piped())
+            .stdout(Stdio::piped())
+            .status()
+            .expect("Couldn't create yacrd subprocess");
+        assert!(status.success());
+
+        let read_lines = |path: &str| -> Vec<String> {
+            std::io::BufReader::new(std::fs::File::open(path).unwrap())
+                .lines()
+                .map(|l| l.unwrap())
+                .filter(|l| !l.starts_with('#') && !l.starts_with("track"))
+                .collect()
+        };
+
+        let bed = read_lines("tests/result.bed.yacrd");
+        assert!(
+            bed.contains(&"d39539e2-c76f-2b3d-6164-5c2bf66e9673\t0\t20\tNotBad\t0\t.".to_string())
+        );
+        assert!(bed.iter().all(|l| l.split('\t').count() == 6));
+
+        let bedgraph = read_lines("tests/result.bedgraph.yacrd");
+        assert!(bedgraph.contains(&"d39539e2-c76f-2b3d-6164-5c2bf66e9673\t0\t20\t0".to_string()));
+        assert!(bedgraph.iter().all(|l| l.split('\t').count() == 4));
+    }
+
+    #[test]
+    fn summary() {
+        let status = Command::new("./target/debug/yacrd")
+            .args([
+                "-i",
+                "tests/reads.paf",
+                "-o",
+                "tests/result.summary.yacrd",
+                "--summary",
+                "tests/result.summary.tsv.yacrd",
+                "--summary-format",
+                "tsv",
+                "split",
+                "-i",
+                "tests/reads.fastq",
+                "-o",
+                "tests/reads.summary.split.fastq",
+            ])
+            .stderr(Stdio::piped())
+            .stdout(Stdio::piped())
+            .status()
+            .expect("Couldn't create yacrd subprocess");
+        assert!(status.success());
+
+        let summary: Vec<String> =
+            std::io::BufReader::new(std::fs::File::open("tests/result.summary.tsv.yacrd").unwrap())
+                .lines()
+                .map(|l| l.unwrap())
+                .collect();
+
+        for line in [
+            "classes.Chimeric.reads\t4",
+            "classes.NotBad.reads\t226",
+            "bad_regions.middle\t4",
+            "sequences.before.reads\t461",
+            "sequences.after.reads\t465",
+        ] {
+            assert!(summary.contains(&line.to_string()), "{} not found", line);
+        }
+    }
+
+    #[test]
+    fn summary_filter() {
+        let status = Command::new("./target/debug/yacrd")
+            .args([
+                "-i",
+                "tests/reads.paf",
+                "-o",
+                "tests/result.summary_filter.yacrd",
+                "--summary",
+                "tests/result.summary_filter.tsv.yacrd",
+                "--summary-format",
+                "tsv",
+                "filter",
+                "-i",
+                "tests/reads.fastq",
+                "-o",
+                "tests/reads.summary.filter.fastq",
+            ])
+            .stderr(Stdio::piped())
+            .stdout(Stdio::piped())
+            .status()
+            .expect("Couldn't create yacrd subprocess");
+        assert!(status.success());
+
+        let summary: Vec<String> = std::io::BufReader::new(
+            std::fs::File::open("tests/result.summary_filter.tsv.yacrd").unwrap(),
+        )
+        .lines()
+        .map(|l| l.unwrap())
+        .collect();
+
+        let filtered = std::fs::read_to_string("tests/truth.filter.fastq")
+            .unwrap()
+            .lines()
+            .count()
+            / 4;
+        for line in [
+            "sequences.before.reads\t461".to_string(),
+            format!("sequences.after.reads\t{}", filtered),
+        ] {
+            assert!(summary.contains(&line), "{} not found", line);
+        }
+
+        let status = Command::new("./target/debug/yacrd")
+            .args([
+                "-i",
+                "tests/reads.paf",
+                "-o",
+                "tests/result.summary_report.yacrd",
+                "--summary",
+                "tests/result.summary_report.tsv.yacrd",
+                "--summary-format",
+                "tsv",
+            ])
+            .stderr(Stdio::piped())
+            .stdout(Stdio::piped())
+            .status()
+            .expect("Couldn't create yacrd subprocess");
+        assert!(status.success());
+
+        let summary = std::fs::read_to_string("tests/result.summary_report.tsv.yacrd").unwrap();
+        assert!(summary.contains("classes.NotBad.reads\t226\n"));
+        assert!(!summary.contains("no_overlap"));
+        assert!(!summary.contains("sequences."));
+    }
+
     #[test]
     fn scrubb() {
         let mut child = Command::new("./target/debug/yacrd")
-            .args(&[
+            .args([
                 "-i",
                 "tests/reads.paf",
                 "-o",
@@ -295,7 +713,7 @@ mod tests {
             panic!();
         }
 
-        diff_unorder("tests/truth.yacrd", "tests/result.scrubb.yacrd");
+        diff_report("tests/truth.yacrd", "tests/result.scrubb.yacrd");
         diff("tests/truth.scrubb.fastq", "tests/reads.scrubb.fastq")
     }
 }